@@ -0,0 +1,431 @@
+//! Chain specs built from a human-friendly genesis description file.
+//!
+//! Instead of a raw chain spec, `--chain` accepts a TOML (`*.toml`) or JSON file describing the
+//! network, for example:
+//!
+//! ```toml
+//! name = "Marketplace Testnet"
+//! id = "marketplace_testnet"
+//! chain_type = "Live"
+//! sudo = "//Alice"
+//! council = ["//Alice", "//Bob"]
+//!
+//! [[telemetry]]
+//! url = "wss://telemetry.polkadot.io/submit/"
+//! verbosity = 0
+//!
+//! [[authorities]]
+//! account = "5GNJqTPyNqANBkUVMN1LPPrxXnFouWXoe2wNSmmEoLctxiZY"
+//! aura = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+//! grandpa = "5FA9nQDVg267DEd8m1ZypXLBnvN7SFxYwV7ndqSYGiN9TTpu"
+//!
+//! [[endowments]]
+//! account = "//Alice"
+//! balance = "1000000000000000000"
+//!
+//! [[vesting]]
+//! account = "//Ferdie"
+//! locked = 500000000000000
+//! per_block = 100000000
+//! start = 0
+//! ```
+//!
+//! Accounts and keys are SS58 addresses, in the chain's or the generic Substrate format, or
+//! `//`-prefixed development seeds. Amounts are in the smallest unit, written as integers or, past
+//! the range of a TOML integer, as decimal strings. The description is validated before any genesis
+//! state is built. The `staging` and `production` presets are descriptions too, see `node/res`,
+//! and templates whose placeholders must be filled in before they build.
+
+use crate::chain_spec::{self, ChainSpec, VestingSchedule};
+use node_template_runtime::{
+	AccountId, Balance, BlockNumber, EXISTENTIAL_DEPOSIT, SS58_PREFIX, WASM_BINARY,
+};
+use sc_service::{config::MultiaddrWithPeerId, ChainType};
+use sc_telemetry::TelemetryEndpoints;
+use serde::Deserialize;
+use sp_consensus_aura::sr25519::AuthorityId as AuraId;
+use sp_core::{crypto::Ss58Codec, ed25519, sr25519, Public};
+use sp_finality_grandpa::AuthorityId as GrandpaId;
+use std::{collections::BTreeSet, fs, path::Path};
+
+/// Address format of generic Substrate chains, accepted alongside the chain's own.
+const GENERIC_SS58_PREFIX: u16 = 42;
+
+/// A genesis description file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainConfig {
+	/// Human-readable network name.
+	pub name: String,
+	/// Network identifier, also used as the chain's data directory name.
+	pub id: String,
+	#[serde(default = "default_chain_type")]
+	pub chain_type: ChainType,
+	#[serde(default)]
+	pub boot_nodes: Vec<MultiaddrWithPeerId>,
+	pub protocol_id: Option<String>,
+	#[serde(default)]
+	pub telemetry: Vec<Telemetry>,
+	/// Block authoring and finality keys of the initial validators.
+	pub authorities: Vec<AuthorityKeys>,
+	/// Network admin. Leave out to launch without a sudo key.
+	pub sudo: Option<String>,
+	/// Initial council and technical committee members.
+	#[serde(default)]
+	pub council: Vec<String>,
+	#[serde(default)]
+	pub endowments: Vec<Endowment>,
+	#[serde(default)]
+	pub vesting: Vec<Vesting>,
+}
+
+/// A telemetry server the node reports to.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Telemetry {
+	pub url: String,
+	#[serde(default)]
+	pub verbosity: u8,
+}
+
+/// An initial validator: its account and session keys.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthorityKeys {
+	/// Owns the session keys and receives the validator's block authoring fees. Unlike the keys,
+	/// it need not live on the validator's machine.
+	pub account: String,
+	pub aura: String,
+	pub grandpa: String,
+}
+
+/// An account pre-funded at genesis.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Endowment {
+	pub account: String,
+	pub balance: Amount,
+}
+
+/// A vesting schedule set up at genesis, see [`VestingSchedule`].
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Vesting {
+	pub account: String,
+	pub locked: Amount,
+	pub per_block: Amount,
+	#[serde(default)]
+	pub start: BlockNumber,
+}
+
+/// A balance written either as an integer or as a decimal string.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "AmountRepr")]
+pub struct Amount(pub Balance);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AmountRepr {
+	Int(u64),
+	Str(String),
+}
+
+impl TryFrom<AmountRepr> for Amount {
+	type Error = String;
+
+	fn try_from(repr: AmountRepr) -> Result<Self, Self::Error> {
+		match repr {
+			AmountRepr::Int(n) => Ok(Amount(n.into())),
+			AmountRepr::Str(s) =>
+				s.parse().map(Amount).map_err(|e| format!("invalid amount {:?}: {}", s, e)),
+		}
+	}
+}
+
+fn default_chain_type() -> ChainType {
+	ChainType::Live
+}
+
+/// Whether `path` holds a genesis description rather than a chain spec: either a `.toml` file, or
+/// JSON without the `genesis` section every chain spec has.
+pub fn is_chain_config(path: &Path) -> bool {
+	if path.extension().map_or(false, |ext| ext == "toml") {
+		return true
+	}
+	fs::read(path)
+		.ok()
+		.and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+		.map_or(false, |json| json.is_object() && json.get("genesis").is_none())
+}
+
+impl ChainConfig {
+	/// Read a description from `path`, as TOML if it has a `.toml` extension and JSON otherwise.
+	pub fn from_file(path: &Path) -> Result<Self, String> {
+		let contents = fs::read_to_string(path)
+			.map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+		let config = if path.extension().map_or(false, |ext| ext == "toml") {
+			Self::from_toml(&contents)
+		} else {
+			serde_json::from_str(&contents).map_err(|e| format!("Error parsing description: {}", e))
+		};
+		config.map_err(|e| format!("{}: {}", path.display(), e))
+	}
+
+	/// Parse a description written in TOML.
+	pub fn from_toml(contents: &str) -> Result<Self, String> {
+		toml::from_str(contents).map_err(|e| format!("Error parsing description: {}", e))
+	}
+
+	/// Check the description and build a chain spec from it.
+	pub fn build(self) -> Result<ChainSpec, String> {
+		let wasm_binary = WASM_BINARY.ok_or_else(|| "Wasm binary not available".to_string())?;
+		let genesis = self.validate()?;
+		let telemetry = if self.telemetry.is_empty() {
+			None
+		} else {
+			let endpoints =
+				self.telemetry.iter().map(|t| (t.url.clone(), t.verbosity)).collect::<Vec<_>>();
+			Some(
+				TelemetryEndpoints::new(endpoints)
+					.map_err(|e| format!("Invalid telemetry endpoint: {}", e))?,
+			)
+		};
+
+		Ok(ChainSpec::from_genesis(
+			&self.name,
+			&self.id,
+			self.chain_type,
+			move || {
+				chain_spec::testnet_genesis(
+					wasm_binary,
+					genesis.authorities.clone(),
+					genesis.sudo.clone(),
+					genesis.council.clone(),
+					genesis.endowments.clone(),
+					genesis.vesting.clone(),
+					true,
+				)
+				.expect("description validated above; qed")
+			},
+			self.boot_nodes,
+			telemetry,
+			self.protocol_id.as_deref(),
+			None,
+			Some(chain_spec::chain_properties()),
+			None,
+		))
+	}
+
+	/// Resolve every account and key, and reject descriptions the runtime would refuse at genesis.
+	fn validate(&self) -> Result<Genesis, String> {
+		if self.authorities.is_empty() {
+			return Err("At least one authority is required".into())
+		}
+		let authorities = self
+			.authorities
+			.iter()
+			.map(|keys| -> Result<(AccountId, AuraId, GrandpaId), String> {
+				Ok((
+					parse_account(&keys.account)?,
+					parse_key::<sr25519::Public>(&keys.aura)?.into(),
+					parse_key::<ed25519::Public>(&keys.grandpa)?.into(),
+				))
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+		ensure_unique(authorities.iter().map(|(account, ..)| account), "validator account")?;
+		ensure_unique(authorities.iter().map(|(_, aura, _)| aura), "aura authority")?;
+		ensure_unique(authorities.iter().map(|(.., grandpa)| grandpa), "grandpa authority")?;
+
+		let sudo = self.sudo.as_deref().map(parse_account).transpose()?;
+		let council =
+			self.council.iter().map(|a| parse_account(a)).collect::<Result<Vec<_>, _>>()?;
+		ensure_unique(council.iter(), "council member")?;
+
+		let endowments = self
+			.endowments
+			.iter()
+			.map(|e| {
+				let who = parse_account(&e.account)?;
+				if e.balance.0 < EXISTENTIAL_DEPOSIT {
+					return Err(format!(
+						"Endowment of {} is below the existential deposit of {}",
+						e.account, EXISTENTIAL_DEPOSIT
+					))
+				}
+				Ok((who, e.balance.0))
+			})
+			.collect::<Result<Vec<_>, String>>()?;
+		ensure_unique(endowments.iter().map(|(who, _)| who), "endowed account")?;
+
+		let vesting = self
+			.vesting
+			.iter()
+			.map(|v| Ok((parse_account(&v.account)?, v.locked.0, v.per_block.0, v.start)))
+			.collect::<Result<Vec<_>, String>>()?;
+		chain_spec::vesting_genesis(&vesting, &endowments)?;
+		ensure_unique(vesting.iter().map(|(who, ..)| who), "vesting account")?;
+
+		Ok(Genesis { authorities, sudo, council, endowments, vesting })
+	}
+}
+
+/// A validated [`ChainConfig`], ready to be turned into genesis state.
+#[derive(Debug)]
+struct Genesis {
+	authorities: Vec<(AccountId, AuraId, GrandpaId)>,
+	sudo: Option<AccountId>,
+	council: Vec<AccountId>,
+	endowments: Vec<(AccountId, Balance)>,
+	vesting: Vec<VestingSchedule>,
+}
+
+/// Parse an SS58 address or a `//`-prefixed development seed.
+fn parse_key<TPublic: Public + Ss58Codec>(s: &str) -> Result<TPublic, String> {
+	ensure_filled_in(s)?;
+	match s.strip_prefix("//") {
+		Some(seed) if !seed.is_empty() => Ok(chain_spec::get_from_seed::<TPublic>(seed)),
+		_ => from_address(s).map_err(|e| format!("Invalid key {:?}: {}", s, e)),
+	}
+}
+
+fn parse_account(s: &str) -> Result<AccountId, String> {
+	ensure_filled_in(s)?;
+	match s.strip_prefix("//") {
+		Some(seed) if !seed.is_empty() =>
+			Ok(chain_spec::get_account_id_from_seed::<sr25519::Public>(seed)),
+		_ => from_address(s).map_err(|e| format!("Invalid account {:?}: {}", s, e)),
+	}
+}
+
+/// Reject `<...>` placeholders, as left in templates like the `staging` and `production` presets.
+fn ensure_filled_in(s: &str) -> Result<(), String> {
+	if s.starts_with('<') && s.ends_with('>') {
+		return Err(format!("Placeholder {} must be replaced with a real address", s))
+	}
+	Ok(())
+}
+
+/// Decode an SS58 address in the chain's own format or the generic Substrate one, rejecting
+/// addresses meant for other networks.
+fn from_address<T: Ss58Codec>(s: &str) -> Result<T, String> {
+	let (key, format) = T::from_ss58check_with_version(s).map_err(|e| format!("{:?}", e))?;
+	match u16::from(format) {
+		SS58_PREFIX | GENERIC_SS58_PREFIX => Ok(key),
+		prefix => Err(format!("address format {} belongs to another network", prefix)),
+	}
+}
+
+fn ensure_unique<'a, T: Ord + Ss58Codec + 'a>(
+	items: impl IntoIterator<Item = &'a T>,
+	what: &str,
+) -> Result<(), String> {
+	let mut seen = BTreeSet::new();
+	for item in items {
+		if !seen.insert(item) {
+			return Err(format!("Duplicate {} {}", what, item.to_ss58check()))
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::crypto::Ss58AddressFormat;
+
+	const VALID: &str = r#"
+		name = "Test"
+		id = "test"
+		sudo = "//Alice"
+		council = ["//Alice", "//Bob"]
+
+		[[authorities]]
+		account = "//Alice//stash"
+		aura = "//Alice"
+		grandpa = "//Alice"
+
+		[[endowments]]
+		account = "//Alice"
+		balance = "1000000000000000000000"
+
+		[[endowments]]
+		account = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty"
+		balance = 1000
+
+		[[vesting]]
+		account = "//Ferdie"
+		locked = 1000000
+		per_block = 10
+	"#;
+
+	fn parse(toml: &str) -> ChainConfig {
+		toml::from_str(toml).expect("test descriptions are well-formed")
+	}
+
+	#[test]
+	fn valid_description_resolves_accounts() {
+		let genesis = parse(VALID).validate().unwrap();
+
+		let alice = chain_spec::get_account_id_from_seed::<sr25519::Public>("Alice");
+		let bob = chain_spec::get_account_id_from_seed::<sr25519::Public>("Bob");
+		let ferdie = chain_spec::get_account_id_from_seed::<sr25519::Public>("Ferdie");
+		assert_eq!(genesis.authorities, vec![chain_spec::authority_keys_from_seed("Alice")]);
+		assert_eq!(genesis.sudo, Some(alice.clone()));
+		assert_eq!(genesis.council, vec![alice.clone(), bob.clone()]);
+		assert_eq!(genesis.endowments, vec![(alice, 1_000_000_000_000_000_000_000), (bob, 1_000)]);
+		assert_eq!(genesis.vesting, vec![(ferdie, 1_000_000, 10, 0)]);
+	}
+
+	#[test]
+	fn addresses_round_trip_in_the_chain_format() {
+		let alice = chain_spec::get_account_id_from_seed::<sr25519::Public>("Alice");
+		let format = Ss58AddressFormat::custom(SS58_PREFIX);
+		let address = alice.to_ss58check_with_version(format);
+
+		assert_ne!(address, "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY");
+		assert_eq!(AccountId::from_ss58check_with_version(&address), Ok((alice.clone(), format)));
+		assert_eq!(parse_account(&address), Ok(alice.clone()));
+		// Generic Substrate addresses are accepted too, other networks' are not.
+		assert_eq!(
+			parse_account("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"),
+			Ok(alice.clone())
+		);
+		let polkadot = alice.to_ss58check_with_version(Ss58AddressFormat::custom(0));
+		assert!(parse_account(&polkadot).unwrap_err().contains("another network"));
+	}
+
+	#[test]
+	fn unknown_sections_are_rejected() {
+		let toml = format!("{}\n[[nft_collections]]\nowner = \"//Alice\"\n", VALID);
+		assert!(toml::from_str::<ChainConfig>(&toml).is_err());
+	}
+
+	#[test]
+	fn invalid_descriptions_are_rejected() {
+		let cases = [
+			(VALID.replace("aura = \"//Alice\"", "aura = \"nonsense\""), "Invalid key"),
+			(VALID.replace("sudo = \"//Alice\"", "sudo = \"<sudo address>\""), "Placeholder"),
+			(VALID.replace("balance = 1000", "balance = 499"), "existential deposit"),
+			(VALID.replace("//Bob", "//Alice"), "Duplicate"),
+			(VALID.replace("per_block = 10", "per_block = 0"), "unlocks nothing"),
+			(
+				VALID
+					.replace("locked = 1000000", "locked = 10000000000")
+					.replace("per_block = 10", "per_block = 1"),
+				"too many",
+			),
+			(
+				VALID.replace("//Ferdie", "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty"),
+				"locks more",
+			),
+		];
+		for (description, error) in cases {
+			let err = parse(&description).validate().unwrap_err();
+			assert!(err.contains(error), "expected {:?}, got {:?}", error, err);
+		}
+
+		let mut config = parse(VALID);
+		config.authorities.clear();
+		assert!(config.validate().is_err());
+	}
+}
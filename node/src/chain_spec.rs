@@ -1,15 +1,28 @@
+use crate::chain_config::ChainConfig;
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, Signature, SudoConfig,
-	SystemConfig, WASM_BINARY,
+	opaque::SessionKeys, AccountId, AuraConfig, Balance, BalancesConfig, BlockNumber,
+	CouncilConfig, GenesisConfig, GrandpaConfig, SessionConfig, Signature, SudoConfig,
+	SystemConfig, TechnicalCommitteeConfig, VestingConfig, DAYS, EXISTENTIAL_DEPOSIT, SS58_PREFIX,
+	WASM_BINARY,
 };
-use sc_service::ChainType;
+use sc_service::{ChainType, Properties};
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{sr25519, Pair, Public};
 use sp_finality_grandpa::AuthorityId as GrandpaId;
 use sp_runtime::traits::{IdentifyAccount, Verify};
 
-// The URL for the telemetry server.
-// const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
+/// Symbol of the native token, as shown by wallets.
+const TOKEN_SYMBOL: &str = "MKT";
+/// Decimals of the native token, matching `UNIT`.
+const TOKEN_DECIMALS: u32 = 12;
+
+/// Balance of every pre-funded account in the development and local testnet specs.
+const ENDOWMENT: Balance = 1 << 60;
+
+/// A genesis vesting schedule: `(account, locked, unlocked per block, starting block)`.
+///
+/// Accounts that are not pre-funded are endowed with exactly the locked amount.
+pub type VestingSchedule = (AccountId, Balance, Balance, BlockNumber);
 
 /// Specialized `ChainSpec`. This is a specialization of the general Substrate ChainSpec type.
 pub type ChainSpec = sc_service::GenericChainSpec<GenesisConfig>;
@@ -31,19 +44,37 @@ where
 	AccountPublic::from(get_from_seed::<TPublic>(seed)).into_account()
 }
 
-/// Generate an Aura authority key.
-pub fn authority_keys_from_seed(s: &str) -> (AuraId, GrandpaId) {
-	(get_from_seed::<AuraId>(s), get_from_seed::<GrandpaId>(s))
+/// Chain properties wallets use to display balances and addresses.
+pub fn chain_properties() -> Properties {
+	let mut properties = Properties::new();
+	properties.insert("tokenSymbol".into(), TOKEN_SYMBOL.into());
+	properties.insert("tokenDecimals".into(), TOKEN_DECIMALS.into());
+	properties.insert("ss58Format".into(), SS58_PREFIX.into());
+	properties
+}
+
+/// Generate a validator's account, the seed's `//stash` account, and its Aura and GRANDPA keys.
+pub fn authority_keys_from_seed(s: &str) -> (AccountId, AuraId, GrandpaId) {
+	(
+		get_account_id_from_seed::<sr25519::Public>(&format!("{}//stash", s)),
+		get_from_seed::<AuraId>(s),
+		get_from_seed::<GrandpaId>(s),
+	)
 }
 
 pub fn development_config() -> Result<ChainSpec, String> {
+	development_spec("Development", "dev")
+}
+
+/// The development chain under another name and id, e.g. for a fork of a live chain.
+pub fn development_spec(name: &str, id: &str) -> Result<ChainSpec, String> {
 	let wasm_binary = WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?;
 
 	Ok(ChainSpec::from_genesis(
 		// Name
-		"Development",
+		name,
 		// ID
-		"dev",
+		id,
 		ChainType::Development,
 		move || {
 			testnet_genesis(
@@ -51,16 +82,21 @@ pub fn development_config() -> Result<ChainSpec, String> {
 				// Initial PoA authorities
 				vec![authority_keys_from_seed("Alice")],
 				// Sudo account
-				get_account_id_from_seed::<sr25519::Public>("Alice"),
+				Some(get_account_id_from_seed::<sr25519::Public>("Alice")),
+				// Council and technical committee members
+				vec![get_account_id_from_seed::<sr25519::Public>("Alice")],
 				// Pre-funded accounts
-				vec![
+				endowed(vec![
 					get_account_id_from_seed::<sr25519::Public>("Alice"),
 					get_account_id_from_seed::<sr25519::Public>("Bob"),
 					get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
 					get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
-				],
+				]),
+				// Vesting schedules
+				vec![],
 				true,
 			)
+			.expect("preset vesting schedules are valid; qed")
 		},
 		// Bootnodes
 		vec![],
@@ -70,7 +106,7 @@ pub fn development_config() -> Result<ChainSpec, String> {
 		None,
 		None,
 		// Properties
-		None,
+		Some(chain_properties()),
 		// Extensions
 		None,
 	))
@@ -91,12 +127,18 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 				// Initial PoA authorities
 				vec![authority_keys_from_seed("Alice"), authority_keys_from_seed("Bob")],
 				// Sudo account
-				get_account_id_from_seed::<sr25519::Public>("Alice"),
-				// Pre-funded accounts
+				Some(get_account_id_from_seed::<sr25519::Public>("Alice")),
+				// Council and technical committee members
 				vec![
 					get_account_id_from_seed::<sr25519::Public>("Alice"),
 					get_account_id_from_seed::<sr25519::Public>("Bob"),
 					get_account_id_from_seed::<sr25519::Public>("Charlie"),
+				],
+				// Pre-funded accounts
+				endowed(vec![
+					get_account_id_from_seed::<sr25519::Public>("Alice"),
+					get_account_id_from_seed::<sr25519::Public>("Bob"),
+					get_account_id_from_seed::<sr25519::Public>("Charlie"),
 					get_account_id_from_seed::<sr25519::Public>("Dave"),
 					get_account_id_from_seed::<sr25519::Public>("Eve"),
 					get_account_id_from_seed::<sr25519::Public>("Ferdie"),
@@ -106,9 +148,17 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 					get_account_id_from_seed::<sr25519::Public>("Dave//stash"),
 					get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
 					get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
-				],
+				]),
+				// Vesting schedules: half of Ferdie's allocation unlocks over a year.
+				vec![(
+					get_account_id_from_seed::<sr25519::Public>("Ferdie"),
+					ENDOWMENT / 2,
+					ENDOWMENT / 2 / (365 * DAYS) as Balance,
+					0,
+				)],
 				true,
 			)
+			.expect("preset vesting schedules are valid; qed")
 		},
 		// Bootnodes
 		vec![],
@@ -116,41 +166,213 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 		None,
 		// Protocol ID
 		None,
-		// Properties
-		None,
 		None,
+		// Properties
+		Some(chain_properties()),
 		// Extensions
 		None,
 	))
 }
 
+/// The public staging network, described by `node/res/staging.toml`. Fails until the template's
+/// placeholder keys and addresses are filled in.
+pub fn staging_config() -> Result<ChainSpec, String> {
+	ChainConfig::from_toml(include_str!("../res/staging.toml"))?.build()
+}
+
+/// The production network, described by `node/res/production.toml`. Fails until the template's
+/// placeholder keys and addresses are filled in.
+pub fn production_config() -> Result<ChainSpec, String> {
+	ChainConfig::from_toml(include_str!("../res/production.toml"))?.build()
+}
+
+/// Endow each of `accounts` with [`ENDOWMENT`].
+fn endowed(accounts: Vec<AccountId>) -> Vec<(AccountId, Balance)> {
+	accounts.into_iter().map(|k| (k, ENDOWMENT)).collect()
+}
+
 /// Configure initial storage state for FRAME modules.
-fn testnet_genesis(
+pub fn testnet_genesis(
 	wasm_binary: &[u8],
-	initial_authorities: Vec<(AuraId, GrandpaId)>,
-	root_key: AccountId,
-	endowed_accounts: Vec<AccountId>,
+	initial_authorities: Vec<(AccountId, AuraId, GrandpaId)>,
+	root_key: Option<AccountId>,
+	governance_members: Vec<AccountId>,
+	endowed_accounts: Vec<(AccountId, Balance)>,
+	vesting: Vec<VestingSchedule>,
 	_enable_println: bool,
-) -> GenesisConfig {
-	GenesisConfig {
+) -> Result<GenesisConfig, String> {
+	let vesting_config = vesting_genesis(&vesting, &endowed_accounts)?;
+	let mut balances = endowed_accounts;
+	for (who, locked, ..) in vesting {
+		if !balances.iter().any(|(k, _)| *k == who) {
+			balances.push((who, locked));
+		}
+	}
+
+	Ok(GenesisConfig {
 		system: SystemConfig {
 			// Add Wasm runtime to storage.
 			code: wasm_binary.to_vec(),
 		},
-		balances: BalancesConfig {
-			// Configure endowed accounts with initial balance of 1 << 60.
-			balances: endowed_accounts.iter().cloned().map(|k| (k, 1 << 60)).collect(),
-		},
-		aura: AuraConfig {
-			authorities: initial_authorities.iter().map(|x| (x.0.clone())).collect(),
-		},
-		grandpa: GrandpaConfig {
-			authorities: initial_authorities.iter().map(|x| (x.1.clone(), 1)).collect(),
+		balances: BalancesConfig { balances },
+		// Each validator's account owns its session keys and receives its block authoring fees.
+		session: SessionConfig {
+			keys: initial_authorities
+				.into_iter()
+				.map(|(validator, aura, grandpa)| {
+					(validator.clone(), validator, SessionKeys { aura, grandpa })
+				})
+				.collect(),
 		},
+		// Aura and GRANDPA get their authorities from the session keys.
+		aura: AuraConfig { authorities: vec![] },
+		grandpa: GrandpaConfig { authorities: vec![] },
 		sudo: SudoConfig {
 			// Assign network admin rights.
-			key: Some(root_key),
+			key: root_key,
 		},
 		transaction_payment: Default::default(),
+		scheduler: Default::default(),
+		council: CouncilConfig { members: governance_members.clone(), phantom: Default::default() },
+		technical_committee: TechnicalCommitteeConfig {
+			members: governance_members,
+			phantom: Default::default(),
+		},
+		democracy: Default::default(),
+		treasury: Default::default(),
+		assets: Default::default(),
+		vesting: VestingConfig { vesting: vesting_config },
+	})
+}
+
+/// Number of blocks a vesting schedule unlocking `per_block` of `locked` every block runs for.
+fn vesting_length(locked: Balance, per_block: Balance) -> Result<BlockNumber, String> {
+	if per_block == 0 {
+		return Err("unlocks nothing per block".into())
+	}
+	let blocks = locked / per_block + Balance::from(locked % per_block != 0);
+	BlockNumber::try_from(blocks)
+		.map_err(|_| format!("runs for {} blocks, too many to count", blocks))
+}
+
+/// Check vesting schedules against the `endowments` and convert them into `pallet_vesting`'s
+/// `(account, begin, length, liquid)` entries, where `liquid` is whatever part of the account's
+/// genesis balance is not locked. Accounts without an endowment are endowed with the locked
+/// amount.
+///
+/// Every schedule `pallet_vesting` would panic on at genesis is rejected here.
+pub fn vesting_genesis(
+	schedules: &[VestingSchedule],
+	endowments: &[(AccountId, Balance)],
+) -> Result<Vec<(AccountId, BlockNumber, BlockNumber, Balance)>, String> {
+	schedules
+		.iter()
+		.map(|(who, locked, per_block, begin)| {
+			let invalid = |e| format!("Vesting schedule for {} {}", who, e);
+			let balance = endowments.iter().find(|(k, _)| k == who).map_or(*locked, |(_, b)| *b);
+			if *locked > balance {
+				return Err(invalid("locks more than its endowment".into()))
+			}
+			if *locked < EXISTENTIAL_DEPOSIT {
+				return Err(invalid(format!(
+					"locks less than the existential deposit of {}",
+					EXISTENTIAL_DEPOSIT
+				)))
+			}
+			let length = vesting_length(*locked, *per_block).map_err(invalid)?;
+			Ok((who.clone(), *begin, length, balance - locked))
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_service::ChainSpec as _;
+	use sp_core::{crypto::Ss58Codec, ed25519};
+	use sp_runtime::BuildStorage;
+
+	#[test]
+	fn presets_build_genesis_storage() {
+		let presets: [(&str, fn() -> Result<ChainSpec, String>); 2] =
+			[("dev", development_config), ("local", local_testnet_config)];
+		for (name, preset) in presets {
+			let spec = preset().unwrap_or_else(|e| panic!("{} spec: {}", name, e));
+			spec.build_storage().unwrap_or_else(|e| panic!("{} genesis: {}", name, e));
+			assert_eq!(spec.properties(), chain_properties(), "{} properties", name);
+		}
+	}
+
+	/// Fill in a preset template's placeholders with development keys and addresses.
+	fn fill_in(template: &str) -> String {
+		let address = |seed: &str| get_account_id_from_seed::<sr25519::Public>(seed).to_ss58check();
+		let mut description = template
+			.replace("<sudo address>", &address("Alice"))
+			.replace("<treasury or foundation address>", &address("Dave"));
+		for (i, seed) in ["Alice", "Bob", "Charlie"].into_iter().enumerate() {
+			description = description
+				.replace(
+					&format!("<validator {} aura key>", i + 1),
+					&get_from_seed::<sr25519::Public>(seed).to_ss58check(),
+				)
+				.replace(
+					&format!("<validator {} grandpa key>", i + 1),
+					&get_from_seed::<ed25519::Public>(seed).to_ss58check(),
+				)
+				.replace(
+					&format!("<validator {} account>", i + 1),
+					&address(&format!("{}//stash", seed)),
+				)
+				.replace(&format!("<council member {} address>", i + 1), &address(seed))
+				.replace(&format!("<endowed account {} address>", i + 1), &address(seed));
+		}
+		description
+	}
+
+	#[test]
+	fn templates_build_once_filled_in() {
+		let templates: [(&str, fn() -> Result<ChainSpec, String>, &str); 2] = [
+			("staging", staging_config, include_str!("../res/staging.toml")),
+			("production", production_config, include_str!("../res/production.toml")),
+		];
+		for (name, preset, template) in templates {
+			match preset() {
+				Err(e) => assert!(e.contains("Placeholder"), "{}: unexpected error {:?}", name, e),
+				Ok(_) => panic!("the {} template must not build with placeholders", name),
+			}
+
+			let description = fill_in(template);
+			assert!(!description.contains("\"<"), "{} placeholders left", name);
+			let spec = ChainConfig::from_toml(&description).unwrap().build().unwrap();
+			spec.build_storage().unwrap_or_else(|e| panic!("{} genesis: {}", name, e));
+			assert_eq!(spec.properties(), chain_properties(), "{} properties", name);
+		}
+	}
+
+	#[test]
+	fn vesting_schedules_are_checked() {
+		let ferdie = get_account_id_from_seed::<sr25519::Public>("Ferdie");
+		let genesis = |locked, per_block| {
+			testnet_genesis(
+				&[],
+				vec![authority_keys_from_seed("Alice")],
+				None,
+				vec![],
+				vec![],
+				vec![(ferdie.clone(), locked, per_block, 0)],
+				false,
+			)
+		};
+
+		let vesting = genesis(1_001, 10).unwrap().vesting.vesting;
+		assert_eq!(vesting, vec![(ferdie.clone(), 0, 101, 0)]);
+		assert!(genesis(1_000, 0).err().unwrap().contains("unlocks nothing"));
+		assert!(genesis(0, 1).err().unwrap().contains("existential deposit"));
+		assert!(genesis(EXISTENTIAL_DEPOSIT - 1, 1)
+			.err()
+			.unwrap()
+			.contains("existential deposit"));
+		let err = genesis(Balance::MAX, 1).err().unwrap();
+		assert!(err.contains("too many"), "unexpected error {:?}", err);
 	}
 }
@@ -1,3 +1,4 @@
+use crate::service::Sealing;
 use sc_cli::RunCmd;
 
 #[derive(Debug, clap::Parser)]
@@ -7,6 +8,14 @@ pub struct Cli {
 
 	#[clap(flatten)]
 	pub run: RunCmd,
+
+	/// Seal blocks without Aura and GRANDPA, for development and tests: `instant` seals a
+	/// block as soon as transactions arrive, `manual` on every `engine_createBlock` RPC call,
+	/// and `interval=<ms>` every given number of milliseconds. Only development and local chains
+	/// can be sealed. Blocks sealed faster than one per slot are stamped ahead of the wall clock
+	/// until it catches up.
+	#[clap(long, value_name = "MODE")]
+	pub sealing: Option<Sealing>,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -27,6 +36,9 @@ pub enum Subcommand {
 	/// Export the state of a given block into a chain spec.
 	ExportState(sc_cli::ExportStateCmd),
 
+	/// Export user state into a development chain spec that boots a local fork.
+	ForkState(crate::fork_state::ForkStateCmd),
+
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
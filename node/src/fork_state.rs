@@ -0,0 +1,337 @@
+//! The `fork-state` subcommand: a development chain spec carrying over a live chain's user state.
+//!
+//! Balances, assets and other user-owned storage are exported from the database, everything that
+//! decides who runs the chain (authorities, sudo, governance) comes from the development genesis,
+//! so the fork boots locally with the well-known dev keys.
+//!
+//! The fork starts again from block 0, so the block numbers stored by the forked pallets are moved
+//! back by the live height: vesting schedules keep what is still locked and start unlocking again
+//! from the fork's first block, while sponsorship rate-limit windows and proxy announcements made
+//! before the fork count as made at its genesis, restarting their windows and delays. Multisig
+//! timepoints keep their live heights, they only identify the operation being approved. Hashes of
+//! live blocks are not carried over, and neither is child storage, which none of the forked
+//! pallets use: a live state that has any is rejected.
+
+use crate::chain_spec;
+use codec::{Codec, Decode, Encode};
+use log::info;
+use node_template_runtime::{AccountId, Balance, BlockNumber, Hash, Index};
+use sc_cli::{BlockNumberOrHash, CliConfiguration, PruningParams, SharedParams};
+use sc_client_api::{StorageProvider, UsageProvider};
+use sc_service::ChainSpec as _;
+use sp_core::{
+	hashing::twox_128,
+	storage::{well_known_keys, Storage},
+};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, ConvertInto, Header as HeaderT},
+	BuildStorage,
+};
+use std::{fmt::Debug, io::Write, str::FromStr, sync::Arc};
+
+type AccountInfo = frame_system::AccountInfo<Index, pallet_balances::AccountData<Balance>>;
+type VestingInfo = pallet_vesting::VestingInfo<Balance, BlockNumber>;
+type SponsorInfo = pallet_sponsorship::SponsorInfo<Balance, BlockNumber>;
+/// A `pallet_proxy::Announcement`, whose fields are private: `(real, call_hash, height)`.
+type Announcement = (AccountId, Hash, BlockNumber);
+
+/// Pallets whose whole state is carried over to the fork. The NFT and marketplace pallets join
+/// this list once they are in the runtime, along with their block numbers in
+/// [`rebase_block_numbers`].
+const FORKED_PALLETS: &[&str] = &[
+	"Balances",
+	"Vesting",
+	"Assets",
+	"Identity",
+	"Multisig",
+	"Proxy",
+	"Sponsorship",
+	"TemplateModule",
+];
+
+/// Single storage items carried over to the fork: accounts, and the last runtime upgrade so the
+/// live runtime does not run its migrations again.
+const FORKED_ITEMS: &[(&str, &str)] = &[("System", "Account"), ("System", "LastRuntimeUpgrade")];
+
+/// The `fork-state` command used to boot a local fork reproducing a live chain's state.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ForkStateCmd {
+	/// Block hash or number of the state to fork. Defaults to the best block.
+	#[clap(value_name = "HASH or NUMBER")]
+	pub input: Option<BlockNumberOrHash>,
+
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl ForkStateCmd {
+	/// Export the forked state and print the fork's raw chain spec to stdout.
+	pub async fn run<B, BA, C>(&self, client: Arc<C>) -> sc_cli::Result<()>
+	where
+		B: BlockT,
+		<B::Hash as FromStr>::Err: Debug,
+		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
+		C: UsageProvider<B> + StorageProvider<B, BA>,
+		BA: sc_client_api::backend::Backend<B>,
+	{
+		info!("Exporting raw state...");
+		let block_id = match &self.input {
+			Some(input) => input.parse()?,
+			None => BlockId::Hash(client.usage_info().chain.best_hash),
+		};
+		let live = sc_service::chain_ops::export_raw_state(client, &block_id)?;
+
+		info!("Generating fork chain spec...");
+		let mut spec = chain_spec::development_spec("Development Fork", "dev_fork")?;
+		let mut storage = spec.build_storage()?;
+		fork_storage(&mut storage, live)?;
+		spec.set_storage(storage);
+
+		let json = sc_service::chain_ops::build_spec(&spec, true)?;
+		if std::io::stdout().write_all(json.as_bytes()).is_err() {
+			let _ = std::io::stderr().write_all(b"Error writing to stdout\n");
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ForkStateCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+}
+
+fn storage_key(pallet: &str, item: &str) -> Vec<u8> {
+	[twox_128(pallet.as_bytes()), twox_128(item.as_bytes())].concat()
+}
+
+fn is_forked(key: &[u8]) -> bool {
+	key == well_known_keys::CODE ||
+		key == well_known_keys::HEAP_PAGES ||
+		FORKED_PALLETS
+			.iter()
+			.any(|pallet| key.starts_with(&twox_128(pallet.as_bytes()))) ||
+		FORKED_ITEMS
+			.iter()
+			.any(|(pallet, item)| key.starts_with(&storage_key(pallet, item)))
+}
+
+/// Replace the forked part of the development genesis `fork` with the `live` state, including the
+/// live runtime code. Development accounts that do not exist on the live chain are kept, and the
+/// total issuance adjusted for their balances, so the dev keys can transact on the fork.
+fn fork_storage(fork: &mut Storage, live: Storage) -> Result<(), String> {
+	if !live.children_default.is_empty() {
+		return Err(format!(
+			"The live state has {} child tries, which cannot be forked",
+			live.children_default.len()
+		))
+	}
+	let height = match live.top.get(&storage_key("System", "Number")) {
+		Some(value) => BlockNumber::decode(&mut &value[..])
+			.map_err(|e| format!("Invalid live block number: {}", e))?,
+		None => 0,
+	};
+
+	let accounts = storage_key("System", "Account");
+	let dev_accounts: Vec<_> = fork
+		.top
+		.iter()
+		.filter(|(key, _)| key.starts_with(&accounts))
+		.map(|(key, value)| (key.clone(), value.clone()))
+		.collect();
+
+	fork.top.retain(|key, _| !is_forked(key));
+	fork.top.extend(live.top.into_iter().filter(|(key, _)| is_forked(key)));
+	rebase_block_numbers(fork, height)?;
+
+	let issuance_key = storage_key("Balances", "TotalIssuance");
+	let mut issuance = match fork.top.get(&issuance_key) {
+		Some(value) => Balance::decode(&mut &value[..])
+			.map_err(|e| format!("Invalid live total issuance: {}", e))?,
+		None => 0,
+	};
+	for (key, value) in dev_accounts {
+		if fork.top.contains_key(&key) {
+			continue
+		}
+		let info = AccountInfo::decode(&mut &value[..])
+			.map_err(|e| format!("Invalid development account: {}", e))?;
+		issuance = issuance.saturating_add(info.data.free).saturating_add(info.data.reserved);
+		fork.top.insert(key, value);
+	}
+	fork.top.insert(issuance_key, issuance.encode());
+
+	Ok(())
+}
+
+/// Move the block numbers stored in the forked state back by the live `height`, the fork
+/// restarting from block 0. Vesting schedules are cut at `height`, so what already vested on the
+/// live chain stays unlocked.
+fn rebase_block_numbers(storage: &mut Storage, height: BlockNumber) -> Result<(), String> {
+	let rebase = |block: BlockNumber| block.saturating_sub(height);
+
+	rebase_values(storage, ("Vesting", "Vesting"), |schedules: Vec<VestingInfo>| {
+		schedules
+			.into_iter()
+			.map(|schedule| {
+				VestingInfo::new(
+					schedule.locked_at::<ConvertInto>(height),
+					schedule.per_block(),
+					rebase(schedule.starting_block()),
+				)
+			})
+			.collect()
+	})?;
+	rebase_values(
+		storage,
+		("Proxy", "Announcements"),
+		|(announcements, deposit): (Vec<Announcement>, Balance)| {
+			let announcements = announcements
+				.into_iter()
+				.map(|(real, call_hash, at)| (real, call_hash, rebase(at)))
+				.collect::<Vec<_>>();
+			(announcements, deposit)
+		},
+	)?;
+	rebase_values(storage, ("Sponsorship", "Sponsors"), |info: SponsorInfo| SponsorInfo {
+		period_start: rebase(info.period_start),
+		..info
+	})
+}
+
+/// Apply `rebase` to every value of the `(pallet, item)` storage map.
+fn rebase_values<T: Codec>(
+	storage: &mut Storage,
+	(pallet, item): (&str, &str),
+	rebase: impl Fn(T) -> T,
+) -> Result<(), String> {
+	let prefix = storage_key(pallet, item);
+	for (_, value) in storage.top.iter_mut().filter(|(key, _)| key.starts_with(&prefix)) {
+		let decoded = T::decode(&mut &value[..])
+			.map_err(|e| format!("Invalid live {}::{} entry: {}", pallet, item, e))?;
+		*value = rebase(decoded).encode();
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::storage::{ChildInfo, StorageChild};
+
+	fn account(id: u8, free: Balance) -> (Vec<u8>, Vec<u8>) {
+		let info = AccountInfo {
+			providers: 1,
+			data: pallet_balances::AccountData { free, ..Default::default() },
+			..Default::default()
+		};
+		([storage_key("System", "Account"), vec![id; 32]].concat(), info.encode())
+	}
+
+	#[test]
+	fn fork_keeps_dev_authorities_and_live_user_state() {
+		let aura = storage_key("Aura", "Authorities");
+		let sudo = storage_key("Sudo", "Key");
+		let number = storage_key("System", "Number");
+		let issuance = storage_key("Balances", "TotalIssuance");
+		let listing = storage_key("TemplateModule", "Something");
+		let code = well_known_keys::CODE.to_vec();
+
+		let mut fork = Storage::default();
+		fork.top.extend([
+			account(1, 500),
+			account(2, 500),
+			(aura.clone(), b"dev authorities".to_vec()),
+			(sudo.clone(), b"alice".to_vec()),
+			(number.clone(), 0u32.encode()),
+			(issuance.clone(), 1_000u128.encode()),
+			(code.clone(), b"dev runtime".to_vec()),
+		]);
+		let mut live = Storage::default();
+		live.top.extend([
+			account(2, 7_000),
+			account(3, 3_000),
+			(aura.clone(), b"live authorities".to_vec()),
+			(number.clone(), 1_234u32.encode()),
+			(issuance.clone(), 10_000u128.encode()),
+			(listing.clone(), 42u32.encode()),
+			(code.clone(), b"live runtime".to_vec()),
+		]);
+
+		fork_storage(&mut fork, live).unwrap();
+
+		assert_eq!(fork.top[&aura], b"dev authorities".to_vec());
+		assert_eq!(fork.top[&sudo], b"alice".to_vec());
+		assert_eq!(fork.top[&number], 0u32.encode());
+		assert_eq!(fork.top[&code], b"live runtime".to_vec());
+		assert_eq!(fork.top[&listing], 42u32.encode());
+		// Live accounts win, dev-only accounts are added to the issuance.
+		let (key, value) = account(1, 500);
+		assert_eq!(fork.top[&key], value);
+		let (key, value) = account(2, 7_000);
+		assert_eq!(fork.top[&key], value);
+		let (key, value) = account(3, 3_000);
+		assert_eq!(fork.top[&key], value);
+		assert_eq!(fork.top[&issuance], 10_500u128.encode());
+	}
+
+	#[test]
+	fn fork_moves_block_numbers_back_to_its_genesis() {
+		let key = |pallet, item| [storage_key(pallet, item), vec![1; 32]].concat();
+		let vesting = key("Vesting", "Vesting");
+		let announcements = key("Proxy", "Announcements");
+		let sponsors = key("Sponsorship", "Sponsors");
+		let sponsor = |period_start| SponsorInfo {
+			budget: 1_000,
+			max_transactions: 10,
+			period: 100,
+			period_start,
+			transactions: 3,
+			deposit: 10,
+		};
+		let announcement =
+			|height| -> Announcement { (AccountId::from([2; 32]), Hash::zero(), height) };
+
+		let mut fork = Storage::default();
+		let mut live = Storage::default();
+		live.top.extend([
+			(storage_key("System", "Number"), 1_000u32.encode()),
+			(
+				vesting.clone(),
+				vec![VestingInfo::new(500, 1, 800), VestingInfo::new(500, 1, 1_200)].encode(),
+			),
+			(announcements.clone(), (vec![announcement(990)], 7u128).encode()),
+			(sponsors.clone(), sponsor(950).encode()),
+		]);
+
+		fork_storage(&mut fork, live).unwrap();
+
+		// 200 of the first schedule vested on the live chain, the second has not started yet.
+		assert_eq!(
+			fork.top[&vesting],
+			vec![VestingInfo::new(300, 1, 0), VestingInfo::new(500, 1, 200)].encode()
+		);
+		assert_eq!(fork.top[&announcements], (vec![announcement(0)], 7u128).encode());
+		assert_eq!(fork.top[&sponsors], sponsor(0).encode());
+	}
+
+	#[test]
+	fn fork_rejects_child_storage() {
+		let mut live = Storage::default();
+		let child_info = ChildInfo::new_default(b"child");
+		live.children_default.insert(
+			child_info.storage_key().to_vec(),
+			StorageChild { data: Default::default(), child_info },
+		);
+
+		assert!(fork_storage(&mut Storage::default(), live).is_err());
+	}
+}
@@ -0,0 +1,711 @@
+//! A keystore whose keys live in a separate signer service.
+//!
+//! Selected with `--keystore-uri`, either `http://host:port/path` or `unix:///path/to/socket`.
+//! Every keystore operation is one JSON-RPC 2.0 request: over HTTP the body of a `POST`, over a
+//! Unix socket a single line answered by a single line.
+//!
+//! | method                   | params                                     | result                          |
+//! |--------------------------|--------------------------------------------|---------------------------------|
+//! | `keystore_keys`          | `[keyType]`                                | `[[cryptoType, public], ...]`   |
+//! | `keystore_generate`      | `[keyType, cryptoType, seed or null]`      | `public`                        |
+//! | `keystore_insert`        | `[keyType, suri, public]`                  | `null`                          |
+//! | `keystore_sign`          | `[keyType, cryptoType, public, message]`   | `signature`, `null` if unknown  |
+//! | `keystore_signPrehashed` | `[keyType, public, message]` (ECDSA)       | `signature`, `null` if unknown  |
+//!
+//! Key types (`aura`, `gran`) and crypto types (`sr25`, `ed25`, `ecds`) are their four-character
+//! ids; keys, messages and signatures are `0x`-prefixed hex. Requests are not encrypted, so the
+//! signer should listen on a Unix socket or on the loopback interface. VRF signing is not
+//! supported.
+//!
+//! The async [`CryptoStore`] methods hand their requests to a dedicated thread, so a slow signer
+//! does not stall the executor. The [`SyncCryptoStore`] methods block their caller for up to
+//! [`TIMEOUT`].
+
+use async_trait::async_trait;
+use futures::{
+	channel::{mpsc, oneshot},
+	executor,
+};
+use serde_json::{json, Value};
+use sp_core::{
+	bytes::{from_hex, to_hex},
+	crypto::{CryptoTypeId, CryptoTypePublicPair, KeyTypeId},
+	ecdsa, ed25519, sr25519,
+};
+use sp_keystore::{
+	vrf::{VRFSignature, VRFTranscriptData},
+	CryptoStore, Error, SyncCryptoStore,
+};
+use std::{
+	fmt,
+	io::{self, BufRead, BufReader, Read, Write},
+	net::{Shutdown, TcpStream},
+	os::unix::net::UnixStream,
+	path::PathBuf,
+	str::FromStr,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
+	thread,
+	time::Duration,
+};
+
+/// How long to wait for the signer service before giving up on a request.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where the signer service listens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Endpoint {
+	/// `host:port` and request path of an HTTP server.
+	Http {
+		authority: String,
+		path: String,
+	},
+	Unix(PathBuf),
+}
+
+impl FromStr for Endpoint {
+	type Err = String;
+
+	fn from_str(uri: &str) -> Result<Self, Self::Err> {
+		if let Some(rest) = uri.strip_prefix("http://") {
+			let (authority, path) = match rest.split_once('/') {
+				Some((authority, path)) => (authority, format!("/{}", path)),
+				None => (rest, "/".into()),
+			};
+			if authority.is_empty() {
+				return Err(format!("No host in keystore URI {:?}", uri))
+			}
+			let authority = if authority.contains(':') {
+				authority.into()
+			} else {
+				format!("{}:80", authority)
+			};
+			Ok(Endpoint::Http { authority, path })
+		} else if let Some(path) = uri.strip_prefix("unix://") {
+			Ok(Endpoint::Unix(path.into()))
+		} else {
+			Err(format!(
+				"Unsupported keystore URI {:?}, expected `http://host:port/path` or `unix:///path`",
+				uri
+			))
+		}
+	}
+}
+
+impl fmt::Display for Endpoint {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Endpoint::Http { authority, path } => write!(f, "http://{}{}", authority, path),
+			Endpoint::Unix(path) => write!(f, "unix://{}", path.display()),
+		}
+	}
+}
+
+impl Endpoint {
+	/// Send a request and wait for the response.
+	fn send(&self, request: &str) -> io::Result<String> {
+		match self {
+			Endpoint::Unix(path) => {
+				let mut stream = UnixStream::connect(path)?;
+				stream.set_read_timeout(Some(TIMEOUT))?;
+				stream.set_write_timeout(Some(TIMEOUT))?;
+				stream.write_all(request.as_bytes())?;
+				stream.write_all(b"\n")?;
+				stream.shutdown(Shutdown::Write)?;
+				let mut response = String::new();
+				BufReader::new(stream).read_line(&mut response)?;
+				Ok(response)
+			},
+			Endpoint::Http { authority, path } => {
+				let mut stream = TcpStream::connect(authority)?;
+				stream.set_read_timeout(Some(TIMEOUT))?;
+				stream.set_write_timeout(Some(TIMEOUT))?;
+				write!(
+					stream,
+					"POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+					 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+					path,
+					authority,
+					request.len(),
+					request
+				)?;
+				let mut response = String::new();
+				stream.read_to_string(&mut response)?;
+				http_body(&response)
+			},
+		}
+	}
+}
+
+/// The body of a successful HTTP response.
+fn http_body(response: &str) -> io::Result<String> {
+	let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+	let (head, body) = response
+		.split_once("\r\n\r\n")
+		.ok_or_else(|| invalid("Malformed HTTP response".into()))?;
+	let mut lines = head.lines();
+	let status = lines.next().unwrap_or_default();
+	if status.split_whitespace().nth(1) != Some("200") {
+		return Err(invalid(format!("Unexpected HTTP status {:?}", status)))
+	}
+	let mut body = body;
+	for (name, value) in lines.filter_map(|line| line.split_once(':')) {
+		let (name, value) = (name.trim(), value.trim());
+		if name.eq_ignore_ascii_case("transfer-encoding") && value != "identity" {
+			return Err(invalid(format!("Unsupported transfer encoding {:?}", value)))
+		}
+		if name.eq_ignore_ascii_case("content-length") {
+			let length: usize = value
+				.parse()
+				.map_err(|_| invalid(format!("Invalid content length {:?}", value)))?;
+			body = body.get(..length).ok_or_else(|| invalid("Truncated HTTP response".into()))?;
+		}
+	}
+	Ok(body.into())
+}
+
+/// A request of an async keystore method, run on the keystore's thread.
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A keystore forwarding every operation to a signer service, see the module docs.
+#[derive(Clone)]
+pub struct RemoteKeystore {
+	endpoint: Endpoint,
+	next_id: Arc<AtomicU64>,
+	worker: mpsc::UnboundedSender<Job>,
+}
+
+impl RemoteKeystore {
+	/// A keystore talking to the signer service at `uri`. The service is first contacted when a
+	/// key is needed.
+	pub fn open(uri: &str) -> Result<Self, String> {
+		let endpoint = uri.parse()?;
+		// The thread exits once the keystore and its queued requests are dropped.
+		let (worker, jobs) = mpsc::unbounded::<Job>();
+		thread::Builder::new()
+			.name("remote-keystore".into())
+			.spawn(move || executor::block_on_stream(jobs).for_each(|job| job()))
+			.map_err(|e| format!("Unable to start the remote keystore thread: {}", e))?;
+		Ok(RemoteKeystore { endpoint, next_id: Arc::new(AtomicU64::new(1)), worker })
+	}
+
+	/// Run `request` on the keystore's thread and wait for it without blocking the executor.
+	async fn run<R: Send + 'static>(
+		&self,
+		request: impl FnOnce(&Self) -> R + Send + 'static,
+	) -> Result<R, Error> {
+		let (sender, receiver) = oneshot::channel();
+		let keystore = self.clone();
+		self.worker
+			.unbounded_send(Box::new(move || {
+				let _ = sender.send(request(&keystore));
+			}))
+			.map_err(|_| Error::Unavailable)?;
+		receiver.await.map_err(|_| Error::Unavailable)
+	}
+
+	fn call(&self, method: &str, params: Value) -> Result<Value, Error> {
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+		let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+		let response = self.endpoint.send(&request.to_string()).map_err(|e| {
+			log::warn!(target: "keystore", "Remote keystore {} unavailable: {}", self.endpoint, e);
+			Error::Unavailable
+		})?;
+		let mut response: Value = serde_json::from_str(&response).map_err(|e| {
+			Error::Other(format!("Invalid response from remote keystore {}: {}", self.endpoint, e))
+		})?;
+		if let Some(error) = response.get("error") {
+			let message = error["message"].as_str().unwrap_or("unknown error");
+			return Err(Error::Other(format!("Remote keystore {}: {}", self.endpoint, message)))
+		}
+		Ok(response["result"].take())
+	}
+
+	fn generate(
+		&self,
+		id: KeyTypeId,
+		crypto: CryptoTypeId,
+		seed: Option<&str>,
+	) -> Result<Vec<u8>, Error> {
+		let public =
+			self.call("keystore_generate", json!([key_type(id), crypto_type(crypto), seed]))?;
+		decode_hex(&public)
+	}
+
+	fn public_keys(&self, id: KeyTypeId, crypto: CryptoTypeId) -> Vec<Vec<u8>> {
+		match SyncCryptoStore::keys(self, id) {
+			Ok(keys) => keys.into_iter().filter(|key| key.0 == crypto).map(|key| key.1).collect(),
+			Err(_) => Vec::new(),
+		}
+	}
+
+	fn signature(&self, method: &str, params: Value) -> Result<Option<Vec<u8>>, Error> {
+		match self.call(method, params)? {
+			Value::Null => Ok(None),
+			signature => decode_hex(&signature).map(Some),
+		}
+	}
+}
+
+fn key_type(id: KeyTypeId) -> String {
+	String::from_utf8_lossy(&id.0).into_owned()
+}
+
+fn crypto_type(id: CryptoTypeId) -> String {
+	String::from_utf8_lossy(&id.0).into_owned()
+}
+
+fn decode_hex(value: &Value) -> Result<Vec<u8>, Error> {
+	value.as_str().and_then(|hex| from_hex(hex).ok()).ok_or_else(|| {
+		Error::ValidationError(format!("Expected hex from remote keystore: {}", value))
+	})
+}
+
+/// Convert the signer's bytes into a key or signature of the expected length.
+fn typed<T: for<'a> TryFrom<&'a [u8]>>(bytes: Vec<u8>) -> Result<T, Error> {
+	T::try_from(&bytes[..]).map_err(|_| {
+		Error::ValidationError("Malformed key or signature from remote keystore".into())
+	})
+}
+
+impl SyncCryptoStore for RemoteKeystore {
+	fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+		self.public_keys(id, sr25519::CRYPTO_ID)
+			.into_iter()
+			.filter_map(|key| typed(key).ok())
+			.collect()
+	}
+
+	fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<sr25519::Public, Error> {
+		typed(self.generate(id, sr25519::CRYPTO_ID, seed)?)
+	}
+
+	fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+		self.public_keys(id, ed25519::CRYPTO_ID)
+			.into_iter()
+			.filter_map(|key| typed(key).ok())
+			.collect()
+	}
+
+	fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ed25519::Public, Error> {
+		typed(self.generate(id, ed25519::CRYPTO_ID, seed)?)
+	}
+
+	fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.public_keys(id, ecdsa::CRYPTO_ID)
+			.into_iter()
+			.filter_map(|key| typed(key).ok())
+			.collect()
+	}
+
+	fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ecdsa::Public, Error> {
+		typed(self.generate(id, ecdsa::CRYPTO_ID, seed)?)
+	}
+
+	fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+		self.call("keystore_insert", json!([key_type(id), suri, to_hex(public, false)]))
+			.map(drop)
+			.map_err(drop)
+	}
+
+	fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		let available = SyncCryptoStore::keys(self, id)?;
+		Ok(keys.into_iter().filter(|key| available.contains(key)).collect())
+	}
+
+	fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		let keys = self.call("keystore_keys", json!([key_type(id)]))?;
+		keys.as_array()
+			.ok_or_else(|| Error::ValidationError(format!("Expected a key list, got {}", keys)))?
+			.iter()
+			.map(|key| {
+				let crypto = key[0]
+					.as_str()
+					.and_then(|crypto| crypto.as_bytes().try_into().ok())
+					.ok_or_else(|| {
+					Error::ValidationError(format!("Invalid crypto type in {}", key))
+				})?;
+				Ok(CryptoTypePublicPair(CryptoTypeId(crypto), decode_hex(&key[1])?))
+			})
+			.collect()
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		public_keys.iter().all(|(public, id)| {
+			SyncCryptoStore::keys(self, *id)
+				.map_or(false, |keys| keys.iter().any(|key| key.1 == *public))
+		})
+	}
+
+	fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> Result<Option<Vec<u8>>, Error> {
+		self.signature(
+			"keystore_sign",
+			json!([key_type(id), crypto_type(key.0), to_hex(&key.1, false), to_hex(msg, false)]),
+		)
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		_key_type: KeyTypeId,
+		_public: &sr25519::Public,
+		_transcript_data: VRFTranscriptData,
+	) -> Result<Option<VRFSignature>, Error> {
+		Err(Error::Other("VRF signing is not supported by the remote keystore".into()))
+	}
+
+	fn ecdsa_sign_prehashed(
+		&self,
+		id: KeyTypeId,
+		public: &ecdsa::Public,
+		msg: &[u8; 32],
+	) -> Result<Option<ecdsa::Signature>, Error> {
+		self.signature(
+			"keystore_signPrehashed",
+			json!([key_type(id), to_hex(public.as_ref(), false), to_hex(msg, false)]),
+		)?
+		.map(typed)
+		.transpose()
+	}
+}
+
+#[async_trait]
+impl CryptoStore for RemoteKeystore {
+	async fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+		self.run(move |keystore| SyncCryptoStore::sr25519_public_keys(keystore, id))
+			.await
+			.unwrap_or_default()
+	}
+
+	async fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<sr25519::Public, Error> {
+		let seed = seed.map(String::from);
+		self.run(move |keystore| {
+			SyncCryptoStore::sr25519_generate_new(keystore, id, seed.as_deref())
+		})
+		.await?
+	}
+
+	async fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+		self.run(move |keystore| SyncCryptoStore::ed25519_public_keys(keystore, id))
+			.await
+			.unwrap_or_default()
+	}
+
+	async fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ed25519::Public, Error> {
+		let seed = seed.map(String::from);
+		self.run(move |keystore| {
+			SyncCryptoStore::ed25519_generate_new(keystore, id, seed.as_deref())
+		})
+		.await?
+	}
+
+	async fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.run(move |keystore| SyncCryptoStore::ecdsa_public_keys(keystore, id))
+			.await
+			.unwrap_or_default()
+	}
+
+	async fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ecdsa::Public, Error> {
+		let seed = seed.map(String::from);
+		self.run(move |keystore| SyncCryptoStore::ecdsa_generate_new(keystore, id, seed.as_deref()))
+			.await?
+	}
+
+	async fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+		let (suri, public) = (suri.to_owned(), public.to_vec());
+		self.run(move |keystore| SyncCryptoStore::insert_unknown(keystore, id, &suri, &public))
+			.await
+			.map_err(drop)?
+	}
+
+	async fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		self.run(move |keystore| SyncCryptoStore::supported_keys(keystore, id, keys))
+			.await?
+	}
+
+	async fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, Error> {
+		self.run(move |keystore| SyncCryptoStore::keys(keystore, id)).await?
+	}
+
+	async fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		let public_keys = public_keys.to_vec();
+		self.run(move |keystore| SyncCryptoStore::has_keys(keystore, &public_keys))
+			.await
+			.unwrap_or(false)
+	}
+
+	async fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> Result<Option<Vec<u8>>, Error> {
+		let (key, msg) = (key.clone(), msg.to_vec());
+		self.run(move |keystore| SyncCryptoStore::sign_with(keystore, id, &key, &msg))
+			.await?
+	}
+
+	async fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		transcript_data: VRFTranscriptData,
+	) -> Result<Option<VRFSignature>, Error> {
+		// Unsupported, so there is no request to wait for.
+		SyncCryptoStore::sr25519_vrf_sign(self, key_type, public, transcript_data)
+	}
+
+	async fn ecdsa_sign_prehashed(
+		&self,
+		id: KeyTypeId,
+		public: &ecdsa::Public,
+		msg: &[u8; 32],
+	) -> Result<Option<ecdsa::Signature>, Error> {
+		let (public, msg) = (ecdsa::Public(public.0), *msg);
+		self.run(move |keystore| SyncCryptoStore::ecdsa_sign_prehashed(keystore, id, &public, &msg))
+			.await?
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_keystore::LocalKeystore;
+	use sp_core::{crypto::key_types::AURA, Pair};
+	use std::{net::TcpListener, os::unix::net::UnixListener, sync::atomic::AtomicUsize};
+
+	const GRANDPA: KeyTypeId = KeyTypeId(*b"gran");
+
+	/// Answer a keystore request from `keystore`, the way a signer service would.
+	fn answer(keystore: &LocalKeystore, request: &str) -> String {
+		let request: Value = serde_json::from_str(request).unwrap();
+		let params = &request["params"];
+		let key_type = |i: usize| KeyTypeId::try_from(params[i].as_str().unwrap()).unwrap();
+		let crypto =
+			|i: usize| CryptoTypeId(params[i].as_str().unwrap().as_bytes().try_into().unwrap());
+		let bytes = |i: usize| from_hex(params[i].as_str().unwrap()).unwrap();
+
+		let result = match request["method"].as_str().unwrap() {
+			"keystore_keys" => Ok(json!(SyncCryptoStore::keys(keystore, key_type(0))
+				.unwrap()
+				.into_iter()
+				.map(|key| json!([crypto_type(key.0), to_hex(&key.1, false)]))
+				.collect::<Vec<_>>())),
+			"keystore_generate" => {
+				let seed = params[2].as_str();
+				match crypto(1) {
+					sr25519::CRYPTO_ID =>
+						Ok(SyncCryptoStore::sr25519_generate_new(keystore, key_type(0), seed)
+							.unwrap()
+							.to_vec()),
+					ed25519::CRYPTO_ID =>
+						Ok(SyncCryptoStore::ed25519_generate_new(keystore, key_type(0), seed)
+							.unwrap()
+							.to_vec()),
+					_ => Err("Unsupported crypto type"),
+				}
+				.map(|public| json!(to_hex(&public, false)))
+			},
+			"keystore_sign" => Ok(SyncCryptoStore::sign_with(
+				keystore,
+				key_type(0),
+				&CryptoTypePublicPair(crypto(1), bytes(2)),
+				&bytes(3),
+			)
+			.unwrap()
+			.map_or(Value::Null, |signature| json!(to_hex(&signature, false)))),
+			_ => Err("Method not found"),
+		};
+		match result {
+			Ok(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+			Err(message) => json!({
+				"jsonrpc": "2.0",
+				"id": request["id"],
+				"error": { "code": -32601, "message": message },
+			}),
+		}
+		.to_string()
+	}
+
+	/// Serve keystore requests over HTTP on a local port, returning the keystore URI.
+	fn http_signer(keystore: Arc<LocalKeystore>) -> String {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let uri = format!("http://{}/sign", listener.local_addr().unwrap());
+		thread::spawn(move || {
+			for stream in listener.incoming() {
+				let mut reader = BufReader::new(stream.unwrap());
+				let mut length = 0;
+				loop {
+					let mut line = String::new();
+					reader.read_line(&mut line).unwrap();
+					if line == "\r\n" {
+						break
+					}
+					if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+						length = value.trim().parse().unwrap();
+					}
+				}
+				let mut request = vec![0; length];
+				reader.read_exact(&mut request).unwrap();
+				let response = answer(&keystore, std::str::from_utf8(&request).unwrap());
+				write!(
+					reader.get_mut(),
+					"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+					response.len(),
+					response
+				)
+				.unwrap();
+			}
+		});
+		uri
+	}
+
+	/// Serve keystore requests on a Unix socket, returning the keystore URI.
+	fn unix_signer(keystore: Arc<LocalKeystore>) -> String {
+		static SOCKETS: AtomicUsize = AtomicUsize::new(0);
+		let path = std::env::temp_dir().join(format!(
+			"remote-keystore-{}-{}.sock",
+			std::process::id(),
+			SOCKETS.fetch_add(1, Ordering::Relaxed)
+		));
+		let _ = std::fs::remove_file(&path);
+		let listener = UnixListener::bind(&path).unwrap();
+		thread::spawn(move || {
+			for stream in listener.incoming() {
+				let mut stream = stream.unwrap();
+				let mut request = String::new();
+				BufReader::new(&stream).read_line(&mut request).unwrap();
+				writeln!(stream, "{}", answer(&keystore, &request)).unwrap();
+			}
+		});
+		format!("unix://{}", path.display())
+	}
+
+	fn signs_with_remote_keys(uri: String) {
+		let remote = RemoteKeystore::open(&uri).unwrap();
+
+		let aura = SyncCryptoStore::sr25519_generate_new(&remote, AURA, None).unwrap();
+		let grandpa = SyncCryptoStore::ed25519_generate_new(&remote, GRANDPA, None).unwrap();
+		assert_eq!(SyncCryptoStore::sr25519_public_keys(&remote, AURA), vec![aura]);
+		assert_eq!(SyncCryptoStore::ed25519_public_keys(&remote, GRANDPA), vec![grandpa]);
+		assert!(SyncCryptoStore::has_keys(
+			&remote,
+			&[(aura.to_vec(), AURA), (grandpa.to_vec(), GRANDPA)]
+		));
+		assert!(!SyncCryptoStore::has_keys(&remote, &[(grandpa.to_vec(), AURA)]));
+		assert_eq!(executor::block_on(CryptoStore::sr25519_public_keys(&remote, AURA)), vec![aura]);
+
+		let message = b"block header";
+		let signature = SyncCryptoStore::sign_with(&remote, AURA, &aura.into(), message)
+			.unwrap()
+			.expect("the signer holds the key");
+		let signature = sr25519::Signature::try_from(&signature[..]).unwrap();
+		assert!(sr25519::Pair::verify(&signature, message, &aura));
+		let signature =
+			executor::block_on(CryptoStore::sign_with(&remote, GRANDPA, &grandpa.into(), message))
+				.unwrap()
+				.expect("the signer holds the key");
+		let signature = ed25519::Signature::try_from(&signature[..]).unwrap();
+		assert!(ed25519::Pair::verify(&signature, message, &grandpa));
+
+		// Keys the signer does not hold sign nothing.
+		let unknown = sr25519::Pair::from_seed(&[1; 32]).public();
+		assert!(matches!(
+			SyncCryptoStore::sign_with(&remote, AURA, &unknown.into(), message),
+			Ok(None)
+		));
+	}
+
+	#[test]
+	fn signs_over_http() {
+		signs_with_remote_keys(http_signer(Arc::new(LocalKeystore::in_memory())));
+	}
+
+	#[test]
+	fn signs_over_unix_socket() {
+		signs_with_remote_keys(unix_signer(Arc::new(LocalKeystore::in_memory())));
+	}
+
+	#[test]
+	fn reports_signer_errors() {
+		let remote =
+			RemoteKeystore::open(&unix_signer(Arc::new(LocalKeystore::in_memory()))).unwrap();
+		assert!(matches!(
+			SyncCryptoStore::ecdsa_generate_new(&remote, AURA, None),
+			Err(Error::Other(message)) if message.contains("Unsupported crypto type")
+		));
+
+		let unreachable = RemoteKeystore::open("unix:///nonexistent/keystore.sock").unwrap();
+		assert!(matches!(SyncCryptoStore::keys(&unreachable, AURA), Err(Error::Unavailable)));
+		assert!(SyncCryptoStore::sr25519_public_keys(&unreachable, AURA).is_empty());
+		assert!(matches!(
+			executor::block_on(CryptoStore::keys(&unreachable, AURA)),
+			Err(Error::Unavailable)
+		));
+	}
+
+	#[test]
+	fn parses_keystore_uris() {
+		assert_eq!(
+			"http://127.0.0.1:8000/keys".parse(),
+			Ok(Endpoint::Http { authority: "127.0.0.1:8000".into(), path: "/keys".into() })
+		);
+		assert_eq!(
+			"http://signer".parse(),
+			Ok(Endpoint::Http { authority: "signer:80".into(), path: "/".into() })
+		);
+		assert_eq!(
+			"unix:///run/signer.sock".parse(),
+			Ok(Endpoint::Unix("/run/signer.sock".into()))
+		);
+		assert!("https://signer".parse::<Endpoint>().is_err());
+		assert!("http:///keys".parse::<Endpoint>().is_err());
+	}
+
+	#[test]
+	fn reads_http_bodies() {
+		let response = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}trailing";
+		assert_eq!(http_body(response).unwrap(), "{}");
+		assert!(http_body("HTTP/1.1 500 Internal Server Error\r\n\r\n{}").is_err());
+		assert!(http_body("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\n{}").is_err());
+	}
+}
@@ -1,11 +1,14 @@
 //! Substrate Node Template CLI library.
 #![warn(missing_docs)]
 
+mod chain_config;
 mod chain_spec;
+mod keystore;
 #[macro_use]
 mod service;
 mod cli;
 mod command;
+mod fork_state;
 mod rpc;
 
 fn main() -> sc_cli::Result<()> {
@@ -0,0 +1,178 @@
+//! `marketplace_dryRun`: preview what an extrinsic would do before signing off on it.
+
+use std::sync::Arc;
+
+use codec::Decode;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use node_template_runtime::{
+	dry_run::{DryRunApi, DryRunResult},
+	opaque::Block,
+	Event, Weight,
+};
+use sc_rpc_api::DenyUnsafe;
+use serde::Serialize;
+use sp_api::{Core, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT},
+};
+
+/// The extrinsic could not be decoded.
+const INVALID_EXTRINSIC: i64 = 1;
+/// The runtime call failed.
+const RUNTIME_ERROR: i64 = 2;
+/// The block to dry run on is unknown.
+const UNKNOWN_BLOCK: i64 = 3;
+
+/// Dry-run RPC methods.
+#[rpc]
+pub trait DryRunRpc<BlockHash> {
+	/// Apply a SCALE-encoded extrinsic in a new block on top of block `at`, the best block by
+	/// default, without committing anything.
+	#[rpc(name = "marketplace_dryRun")]
+	fn dry_run(&self, extrinsic: Bytes, at: Option<BlockHash>) -> Result<DryRunOutcome>;
+}
+
+/// The outcome of a dry run.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunOutcome {
+	/// Whether the extrinsic could be included in a block.
+	pub valid: bool,
+	/// Whether its call dispatched successfully.
+	pub success: bool,
+	/// Why it is invalid or failed, e.g. `Balances::InsufficientBalance`.
+	pub error: Option<String>,
+	/// Deposited events, fee payment included.
+	pub events: Vec<String>,
+	/// Weight the extrinsic used.
+	pub weight: Weight,
+}
+
+impl From<DryRunResult<Event>> for DryRunOutcome {
+	fn from(outcome: DryRunResult<Event>) -> Self {
+		let error = match (&outcome.result, outcome.module_error) {
+			(Err(invalid), _) => Some(format!("{:?}", invalid)),
+			(Ok(Err(_)), Some((pallet, error))) => Some(format!(
+				"{}::{}",
+				String::from_utf8_lossy(&pallet),
+				String::from_utf8_lossy(&error)
+			)),
+			(Ok(Err(error)), None) => Some(format!("{:?}", error)),
+			(Ok(Ok(())), _) => None,
+		};
+		DryRunOutcome {
+			valid: outcome.result.is_ok(),
+			success: matches!(outcome.result, Ok(Ok(()))),
+			error,
+			events: outcome.events.iter().map(|event| format!("{:?}", event)).collect(),
+			weight: outcome.weight,
+		}
+	}
+}
+
+/// Dry runs against the client's runtime.
+pub struct DryRun<C> {
+	client: Arc<C>,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<C> DryRun<C> {
+	/// Create a new dry-run RPC handler.
+	pub fn new(client: Arc<C>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { client, deny_unsafe }
+	}
+}
+
+impl<C> DryRunRpc<<Block as BlockT>::Hash> for DryRun<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: DryRunApi<Block, Event>,
+{
+	fn dry_run(
+		&self,
+		extrinsic: Bytes,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<DryRunOutcome> {
+		// Applying extrinsics is as expensive as block production.
+		self.deny_unsafe.check_if_safe()?;
+
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let extrinsic =
+			<Block as BlockT>::Extrinsic::decode(&mut &*extrinsic).map_err(|e| RpcError {
+				code: ErrorCode::ServerError(INVALID_EXTRINSIC),
+				message: "Unable to decode extrinsic".into(),
+				data: Some(e.to_string().into()),
+			})?;
+		let parent =
+			self.client.header(BlockId::hash(at)).ok().flatten().ok_or_else(|| RpcError {
+				code: ErrorCode::ServerError(UNKNOWN_BLOCK),
+				message: "Unknown block".into(),
+				data: Some(format!("{:?}", at).into()),
+			})?;
+		let runtime_error = |e: sp_api::ApiError| RpcError {
+			code: ErrorCode::ServerError(RUNTIME_ERROR),
+			message: "Unable to dry run extrinsic".into(),
+			data: Some(e.to_string().into()),
+		};
+
+		// Like the transaction pool and block production, apply the extrinsic in a child block so
+		// its number, block hashes, weight and events are those of the block it would go into.
+		let child = <<Block as BlockT>::Header as HeaderT>::new(
+			*parent.number() + 1,
+			Default::default(),
+			Default::default(),
+			at,
+			Default::default(),
+		);
+		let api = self.client.runtime_api();
+		api.initialize_block(&BlockId::hash(at), &child).map_err(runtime_error)?;
+		let outcome = api.dry_run(&BlockId::hash(at), extrinsic).map_err(runtime_error)?;
+
+		Ok(outcome.into())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_runtime::{
+		transaction_validity::{InvalidTransaction, TransactionValidityError},
+		DispatchError,
+	};
+
+	fn outcome(
+		result: sp_runtime::ApplyExtrinsicResult,
+		module_error: Option<(&[u8], &[u8])>,
+	) -> DryRunOutcome {
+		DryRunResult {
+			result,
+			module_error: module_error.map(|(pallet, error)| (pallet.to_vec(), error.to_vec())),
+			events: vec![],
+			weight: 10,
+		}
+		.into()
+	}
+
+	#[test]
+	fn outcomes_name_the_error() {
+		let ok = outcome(Ok(Ok(())), None);
+		assert!(ok.valid && ok.success);
+		assert_eq!(ok.error, None);
+
+		let failed = outcome(
+			Ok(Err(DispatchError::Other("unused"))),
+			Some((&b"Balances"[..], &b"InsufficientBalance"[..])),
+		);
+		assert!(failed.valid && !failed.success);
+		assert_eq!(failed.error.as_deref(), Some("Balances::InsufficientBalance"));
+
+		let invalid =
+			outcome(Err(TransactionValidityError::Invalid(InvalidTransaction::Payment)), None);
+		assert!(!invalid.valid && !invalid.success);
+		assert_eq!(invalid.error.as_deref(), Some("Invalid(Payment)"));
+	}
+}
@@ -1,16 +1,24 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
-use node_template_runtime::{self, opaque::Block, RuntimeApi};
-use sc_client_api::{BlockBackend, ExecutorProvider};
+use crate::keystore::RemoteKeystore;
+use codec::Decode;
+use futures::{channel::mpsc, stream, StreamExt};
+use node_template_runtime::{self, opaque::Block, Hash, RuntimeApi};
+use sc_client_api::{BlockBackend, ExecutorProvider, StorageProvider};
 use sc_consensus_aura::{ImportQueueParams, SlotProportion, StartAuraParams};
+use sc_consensus_manual_seal::{
+	consensus::aura::AuraConsensusDataProvider, EngineCommand, ManualSealParams,
+};
 pub use sc_executor::NativeElseWasmExecutor;
 use sc_finality_grandpa::SharedVoterState;
-use sc_keystore::LocalKeystore;
-use sc_service::{error::Error as ServiceError, Configuration, TaskManager};
+use sc_service::{error::Error as ServiceError, ChainType, Configuration, TaskManager};
 use sc_telemetry::{Telemetry, TelemetryWorker};
+use sc_transaction_pool_api::TransactionPool;
 use sp_consensus::SlotData;
 use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
-use std::{sync::Arc, time::Duration};
+use sp_core::{storage::StorageKey, twox_128};
+use sp_runtime::generic::BlockId;
+use std::{str::FromStr, sync::Arc, time::Duration};
 
 // Our native executor instance.
 pub struct ExecutorDispatch;
@@ -32,6 +40,50 @@ impl sc_executor::NativeExecutionDispatch for ExecutorDispatch {
 	}
 }
 
+/// How a development node seals blocks when it runs without Aura and GRANDPA.
+///
+/// Sealed blocks are imported without verification, so sealing is refused on anything but
+/// development and local chains. Every block gets a new Aura slot, so blocks sealed faster than
+/// one per slot are stamped ahead of the wall clock until it catches up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sealing {
+	/// A block as soon as transactions enter the pool.
+	Instant,
+	/// A block for every `engine_createBlock` RPC call.
+	Manual,
+	/// A block every given number of milliseconds.
+	Interval(u64),
+}
+
+impl Sealing {
+	/// Whether a chain of `chain_type` may be sealed, see [`Sealing`].
+	pub fn allowed_on(chain_type: &ChainType) -> bool {
+		matches!(chain_type, ChainType::Development | ChainType::Local)
+	}
+}
+
+impl FromStr for Sealing {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"instant" => Ok(Sealing::Instant),
+			"manual" => Ok(Sealing::Manual),
+			_ => s
+				.strip_prefix("interval=")
+				.and_then(|millis| millis.parse().ok())
+				.filter(|millis| *millis > 0)
+				.map(Sealing::Interval)
+				.ok_or_else(|| {
+					format!(
+						"Invalid sealing mode {:?}, expected `instant`, `manual` or `interval=<ms>`",
+						s
+					)
+				}),
+		}
+	}
+}
+
 type FullClient =
 	sc_service::TFullClient<Block, RuntimeApi, NativeElseWasmExecutor<ExecutorDispatch>>;
 type FullBackend = sc_service::TFullBackend<Block>;
@@ -39,6 +91,7 @@ type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
 
 pub fn new_partial(
 	config: &Configuration,
+	sealing: Option<Sealing>,
 ) -> Result<
 	sc_service::PartialComponents<
 		FullClient,
@@ -59,8 +112,12 @@ pub fn new_partial(
 	>,
 	ServiceError,
 > {
-	if config.keystore_remote.is_some() {
-		return Err(ServiceError::Other("Remote Keystores are not supported.".into()))
+	if sealing.is_some() && !Sealing::allowed_on(&config.chain_spec.chain_type()) {
+		return Err(ServiceError::Other(format!(
+			"Sealing imports blocks without verification and is only allowed on development and \
+			 local chains, not on {:?}",
+			config.chain_spec.name()
+		)))
 	}
 
 	let telemetry = config
@@ -113,7 +170,14 @@ pub fn new_partial(
 
 	let slot_duration = sc_consensus_aura::slot_duration(&*client)?.slot_duration();
 
-	let import_queue =
+	// Sealed blocks carry no Aura seal, so they are imported without verification.
+	let import_queue = if sealing.is_some() {
+		sc_consensus_manual_seal::import_queue(
+			Box::new(client.clone()),
+			&task_manager.spawn_essential_handle(),
+			config.prometheus_registry(),
+		)
+	} else {
 		sc_consensus_aura::import_queue::<AuraPair, _, _, _, _, _, _>(ImportQueueParams {
 			block_import: grandpa_block_import.clone(),
 			justification_import: Some(Box::new(grandpa_block_import.clone())),
@@ -136,7 +200,8 @@ pub fn new_partial(
 			registry: config.prometheus_registry(),
 			check_for_equivocation: Default::default(),
 			telemetry: telemetry.as_ref().map(|x| x.handle()),
-		})?;
+		})?
+	};
 
 	Ok(sc_service::PartialComponents {
 		client,
@@ -150,15 +215,16 @@ pub fn new_partial(
 	})
 }
 
-fn remote_keystore(_url: &String) -> Result<Arc<LocalKeystore>, &'static str> {
-	// FIXME: here would the concrete keystore be built,
-	//        must return a concrete type (NOT `LocalKeystore`) that
-	//        implements `CryptoStore` and `SyncCryptoStore`
-	Err("Remote Keystore not supported.")
+fn remote_keystore(url: &str) -> Result<Arc<RemoteKeystore>, String> {
+	RemoteKeystore::open(url).map(Arc::new)
 }
 
-/// Builds a new service for a full client.
-pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError> {
+/// Builds a new service for a full client. With `sealing`, blocks are sealed by the node itself
+/// instead of Aura and GRANDPA, see [`Sealing`].
+pub fn new_full(
+	mut config: Configuration,
+	sealing: Option<Sealing>,
+) -> Result<TaskManager, ServiceError> {
 	let sc_service::PartialComponents {
 		client,
 		backend,
@@ -168,7 +234,7 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		select_chain,
 		transaction_pool,
 		other: (block_import, grandpa_link, mut telemetry),
-	} = new_partial(&config)?;
+	} = new_partial(&config, sealing)?;
 
 	if let Some(url) = &config.keystore_remote {
 		match remote_keystore(url) {
@@ -222,13 +288,25 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
 
+	let (command_sink, commands_stream) = match sealing {
+		Some(_) => {
+			let (sink, stream) = mpsc::channel(1024);
+			(Some(sink), Some(stream))
+		},
+		None => (None, None),
+	};
+
 	let rpc_extensions_builder = {
 		let client = client.clone();
 		let pool = transaction_pool.clone();
 
 		Box::new(move |deny_unsafe, _| {
-			let deps =
-				crate::rpc::FullDeps { client: client.clone(), pool: pool.clone(), deny_unsafe };
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				pool: pool.clone(),
+				deny_unsafe,
+				command_sink: command_sink.clone(),
+			};
 
 			Ok(crate::rpc::create_full(deps))
 		})
@@ -247,7 +325,71 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		telemetry: telemetry.as_mut(),
 	})?;
 
-	if role.is_authority() {
+	if let (Some(sealing), Some(commands_stream)) = (sealing, commands_stream) {
+		let proposer_factory = sc_basic_authorship::ProposerFactory::new(
+			task_manager.spawn_handle(),
+			client.clone(),
+			transaction_pool.clone(),
+			prometheus_registry.as_ref(),
+			telemetry.as_ref().map(|x| x.handle()),
+		);
+
+		// RPC commands seal blocks in every mode, the instant and interval modes add their own.
+		let commands_stream = match sealing {
+			Sealing::Manual => commands_stream.boxed(),
+			Sealing::Instant => stream::select(
+				commands_stream,
+				transaction_pool.import_notification_stream().map(|_| seal_command(false)),
+			)
+			.boxed(),
+			Sealing::Interval(millis) => stream::select(
+				commands_stream,
+				stream::unfold((), move |()| async move {
+					futures_timer::Delay::new(Duration::from_millis(millis)).await;
+					Some((seal_command(true), ()))
+				}),
+			)
+			.boxed(),
+		};
+
+		let slot_millis =
+			sc_consensus_aura::slot_duration(&*client)?.slot_duration().as_millis() as u64;
+		// `Timestamp::Now`, the parent block's timestamp.
+		let timestamp_key = StorageKey([twox_128(b"Timestamp"), twox_128(b"Now")].concat());
+		let timestamp_client = client.clone();
+
+		let manual_seal = sc_consensus_manual_seal::run_manual_seal(ManualSealParams {
+			block_import: client.clone(),
+			env: proposer_factory,
+			client: client.clone(),
+			pool: transaction_pool,
+			commands_stream,
+			select_chain,
+			consensus_data_provider: Some(Box::new(AuraConsensusDataProvider::new(client.clone()))),
+			create_inherent_data_providers: move |parent, ()| {
+				let parent_timestamp = timestamp_client
+					.storage(&BlockId::Hash(parent), &timestamp_key)
+					.ok()
+					.flatten()
+					.and_then(|data| u64::decode(&mut &data.0[..]).ok())
+					.unwrap_or_default();
+				let timestamp = sealed_timestamp(
+					*sp_timestamp::Timestamp::current(),
+					parent_timestamp,
+					slot_millis,
+				);
+				async move { Ok(sp_timestamp::InherentDataProvider::new(timestamp.into())) }
+			},
+		});
+
+		// the sealing task is considered essential, i.e. if it
+		// fails we take down the service with it.
+		task_manager.spawn_essential_handle().spawn_blocking(
+			"manual-seal",
+			Some("block-authoring"),
+			manual_seal,
+		);
+	} else if role.is_authority() {
 		let proposer_factory = sc_basic_authorship::ProposerFactory::new(
 			task_manager.spawn_handle(),
 			client.clone(),
@@ -316,7 +458,7 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		protocol_name: grandpa_protocol_name,
 	};
 
-	if enable_grandpa {
+	if enable_grandpa && sealing.is_none() {
 		// start the full GRANDPA voter
 		// NOTE: non-authorities could run the GRANDPA observer protocol, but at
 		// this point the full voter should provide better guarantees of block
@@ -345,3 +487,48 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	network_starter.start_network();
 	Ok(task_manager)
 }
+
+/// The timestamp of a block sealed at `now` on a parent stamped `parent`: the start of the current
+/// slot, or the slot after the parent's if the chain has run ahead of the wall clock. Starting
+/// from the parent rather than the wall clock keeps timestamps increasing across restarts.
+fn sealed_timestamp(now: u64, parent: u64, slot_millis: u64) -> u64 {
+	(now - now % slot_millis).max(parent + slot_millis)
+}
+
+/// A command sealing a block on top of the best block, and finalizing it right away.
+fn seal_command(create_empty: bool) -> EngineCommand<Hash> {
+	EngineCommand::SealNewBlock { create_empty, finalize: true, parent_hash: None, sender: None }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn sealing_modes_parse() {
+		assert_eq!("instant".parse(), Ok(Sealing::Instant));
+		assert_eq!("manual".parse(), Ok(Sealing::Manual));
+		assert_eq!("interval=250".parse(), Ok(Sealing::Interval(250)));
+		for invalid in ["", "aura", "interval", "interval=", "interval=0", "interval=-1"] {
+			assert!(invalid.parse::<Sealing>().is_err(), "{:?} parsed", invalid);
+		}
+	}
+
+	#[test]
+	fn sealed_blocks_get_a_new_slot_and_catch_up_with_the_clock() {
+		// On time, blocks are stamped at the start of the current slot.
+		assert_eq!(sealed_timestamp(12_345, 6_000, 6_000), 12_000);
+		// Sealed faster than one per slot, or after a restart behind the chain's time, a block
+		// is stamped one slot after its parent.
+		assert_eq!(sealed_timestamp(12_345, 12_000, 6_000), 18_000);
+		assert_eq!(sealed_timestamp(12_345, 60_000, 6_000), 66_000);
+	}
+
+	#[test]
+	fn sealing_is_limited_to_development_chains() {
+		assert!(Sealing::allowed_on(&ChainType::Development));
+		assert!(Sealing::allowed_on(&ChainType::Local));
+		assert!(!Sealing::allowed_on(&ChainType::Live));
+		assert!(!Sealing::allowed_on(&ChainType::Custom("staging".into())));
+	}
+}
@@ -0,0 +1,57 @@
+//! Benchmarking setup for pallet-sponsorship
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Sponsorship;
+use frame_benchmarking::{account, benchmarks, whitelisted_caller};
+use frame_support::traits::Currency;
+use frame_system::RawOrigin;
+use sp_runtime::traits::Bounded;
+
+/// An account with enough funds for every deposit.
+fn funded<T: Config>(account: T::AccountId) -> T::AccountId {
+	T::Currency::make_free_balance_be(&account, BalanceOf::<T>::max_value() / 2u32.into());
+	account
+}
+
+benchmarks! {
+	register_sponsor {
+		let caller: T::AccountId = funded::<T>(whitelisted_caller());
+		let budget = BalanceOf::<T>::max_value();
+	}: _(RawOrigin::Signed(caller.clone()), budget, 10, 10u32.into())
+	verify {
+		assert!(Sponsors::<T>::contains_key(&caller));
+	}
+
+	sponsor_account {
+		let caller: T::AccountId = funded::<T>(whitelisted_caller());
+		let who: T::AccountId = account("who", 0, 0);
+		Sponsorship::<T>::register_sponsor(
+			RawOrigin::Signed(caller.clone()).into(),
+			BalanceOf::<T>::max_value(),
+			10,
+			10u32.into(),
+		)?;
+	}: _(RawOrigin::Signed(caller.clone()), who.clone())
+	verify {
+		assert_eq!(SponsorOf::<T>::get(&who).map(|sponsored| sponsored.sponsor), Some(caller));
+	}
+
+	leave_sponsorship {
+		let sponsor: T::AccountId = funded::<T>(account("sponsor", 0, 0));
+		let caller: T::AccountId = whitelisted_caller();
+		Sponsorship::<T>::register_sponsor(
+			RawOrigin::Signed(sponsor.clone()).into(),
+			BalanceOf::<T>::max_value(),
+			10,
+			10u32.into(),
+		)?;
+		Sponsorship::<T>::sponsor_account(RawOrigin::Signed(sponsor).into(), caller.clone())?;
+	}: _(RawOrigin::Signed(caller.clone()))
+	verify {
+		assert!(!SponsorOf::<T>::contains_key(&caller));
+	}
+
+	impl_benchmark_test_suite!(Sponsorship, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,168 @@
+//! The signed extension charging sponsored transaction fees.
+
+use crate::{BalanceOf, Config, Pallet};
+use codec::{Decode, Encode};
+use frame_support::weights::{DispatchInfo, PostDispatchInfo};
+use pallet_transaction_payment::ChargeTransactionPayment;
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{DispatchInfoOf, Dispatchable, PostDispatchInfoOf, SignedExtension, Zero},
+	transaction_validity::{InvalidTransaction, TransactionValidity, TransactionValidityError},
+	DispatchResult, FixedPointOperand,
+};
+use sp_std::{fmt, marker::PhantomData};
+
+/// Charge the transaction fee either to the signer's sponsor or, through `S`, to the signer.
+///
+/// `S` is the fee-charging extension the runtime would otherwise use. When
+/// [`Pallet::sponsor_for`] finds a sponsor, the fee is withdrawn from the sponsor via
+/// `pallet_transaction_payment` and taken from its budget; the tip and fee asset chosen in `S`
+/// only apply to transactions the signer pays for. A sponsored call whose sponsor can no longer
+/// pay is rejected rather than charged to the signer, who can `leave_sponsorship` to pay again.
+/// Otherwise `S` handles the fee. The wrapper encodes exactly like `S`, so clients build it the
+/// same way.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct ChargeSponsoredTransactionPayment<T: Config, S>(S, PhantomData<T>);
+
+impl<T: Config, S> From<S> for ChargeSponsoredTransactionPayment<T, S> {
+	fn from(inner: S) -> Self {
+		Self(inner, PhantomData)
+	}
+}
+
+impl<T: Config, S: fmt::Debug> fmt::Debug for ChargeSponsoredTransactionPayment<T, S> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "ChargeSponsoredTransactionPayment({:?})", self.0)
+	}
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+		Ok(())
+	}
+}
+
+/// What [`ChargeSponsoredTransactionPayment`] hands from `pre_dispatch` to `post_dispatch`.
+pub enum SponsoredPre<T, S>
+where
+	T: Config,
+	S: SignedExtension,
+	ChargeTransactionPayment<T>: SignedExtension,
+{
+	/// The fee was withdrawn from `sponsor` for `who`, and `fee` was taken from its budget.
+	Sponsored {
+		sponsor: T::AccountId,
+		who: T::AccountId,
+		fee: BalanceOf<T>,
+		pre: <ChargeTransactionPayment<T> as SignedExtension>::Pre,
+	},
+	/// The inner extension charged the signer.
+	Unsponsored(S::Pre),
+}
+
+impl<T, S> Default for SponsoredPre<T, S>
+where
+	T: Config,
+	S: SignedExtension,
+	S::Pre: Default,
+	ChargeTransactionPayment<T>: SignedExtension,
+{
+	fn default() -> Self {
+		Self::Unsponsored(Default::default())
+	}
+}
+
+impl<T, S> ChargeSponsoredTransactionPayment<T, S>
+where
+	T: Config,
+	BalanceOf<T>: FixedPointOperand,
+	<T as frame_system::Config>::Call: Dispatchable<Info = DispatchInfo>,
+{
+	/// The fee a sponsor pays for a transaction: the regular fee without any tip.
+	fn sponsored_fee(
+		info: &DispatchInfoOf<<T as frame_system::Config>::Call>,
+		len: usize,
+	) -> BalanceOf<T> {
+		pallet_transaction_payment::Pallet::<T>::compute_fee(len as u32, info, Zero::zero())
+	}
+}
+
+impl<T, S> SignedExtension for ChargeSponsoredTransactionPayment<T, S>
+where
+	T: Config + Send + Sync,
+	S: SignedExtension<AccountId = T::AccountId, Call = <T as frame_system::Config>::Call>,
+	S::Pre: Default,
+	BalanceOf<T>: Send + Sync + FixedPointOperand,
+	<T as frame_system::Config>::Call:
+		Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+	ChargeTransactionPayment<T>:
+		SignedExtension<AccountId = T::AccountId, Call = <T as frame_system::Config>::Call>,
+{
+	const IDENTIFIER: &'static str = "ChargeSponsoredTransactionPayment";
+	type AccountId = T::AccountId;
+	type Call = <T as frame_system::Config>::Call;
+	type AdditionalSigned = S::AdditionalSigned;
+	type Pre = SponsoredPre<T, S>;
+
+	fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> {
+		self.0.additional_signed()
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		match Pallet::<T>::sponsor_for(who, call, Self::sponsored_fee(info, len)) {
+			Some(sponsor) => ChargeTransactionPayment::<T>::from(Zero::zero())
+				.validate(&sponsor, call, info, len),
+			None if Pallet::<T>::is_sponsored(who, call) => Err(InvalidTransaction::Payment.into()),
+			None => self.0.validate(who, call, info, len),
+		}
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		let fee = Self::sponsored_fee(info, len);
+		match Pallet::<T>::sponsor_for(who, call, fee) {
+			Some(sponsor) => {
+				let pre = ChargeTransactionPayment::<T>::from(Zero::zero())
+					.pre_dispatch(&sponsor, call, info, len)?;
+				Pallet::<T>::note_sponsored(&sponsor, fee);
+				Ok(SponsoredPre::Sponsored { sponsor, who: who.clone(), fee, pre })
+			},
+			None if Pallet::<T>::is_sponsored(who, call) => Err(InvalidTransaction::Payment.into()),
+			None => Ok(SponsoredPre::Unsponsored(self.0.pre_dispatch(who, call, info, len)?)),
+		}
+	}
+
+	fn post_dispatch(
+		pre: Self::Pre,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		len: usize,
+		result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		match pre {
+			SponsoredPre::Sponsored { sponsor, who, fee, pre } => {
+				ChargeTransactionPayment::<T>::post_dispatch(pre, info, post_info, len, result)?;
+				let actual_fee = pallet_transaction_payment::Pallet::<T>::compute_actual_fee(
+					len as u32,
+					info,
+					post_info,
+					Zero::zero(),
+				);
+				Pallet::<T>::note_fee_paid(sponsor, who, fee, actual_fee);
+				Ok(())
+			},
+			SponsoredPre::Unsponsored(pre) => S::post_dispatch(pre, info, post_info, len, result),
+		}
+	}
+}
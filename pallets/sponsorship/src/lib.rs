@@ -0,0 +1,329 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// Lets registered sponsor accounts pay the transaction fees of the accounts they sponsor.
+///
+/// A sponsor registers with a fee budget and a rate limit, then adds the accounts it is
+/// willing to pay for, reserving a deposit for its registration and for every account. The fee
+/// itself is charged by the [`ChargeSponsoredTransactionPayment`] signed extension: calls
+/// accepted by `Config::SponsorableCall` from a sponsored account are paid by the sponsor
+/// while it has budget and rate left, everything else is paid by the signer as usual.
+///
+/// A sponsored account can leave its sponsor at any time, and a sponsor that can no longer pay
+/// (see `Config::MinimumBudget`) loses its accounts to any sponsor that adds them.
+pub use extension::{ChargeSponsoredTransactionPayment, SponsoredPre};
+pub use pallet::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+mod extension;
+
+/// The balance type `pallet_transaction_payment` charges fees in.
+pub type BalanceOf<T> = <<T as pallet_transaction_payment::Config>::OnChargeTransaction as
+	pallet_transaction_payment::OnChargeTransaction<T>>::Balance;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::BalanceOf;
+	use frame_support::{
+		pallet_prelude::*,
+		traits::{Contains, Currency, ReservableCurrency},
+	};
+	use frame_system::pallet_prelude::*;
+	use sp_runtime::traits::{Saturating, Zero};
+
+	/// Fee budget and rate limit of a sponsor.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub struct SponsorInfo<Balance, BlockNumber> {
+		/// Fees the sponsor is still willing to pay.
+		pub budget: Balance,
+		/// Maximum number of sponsored transactions per `period`.
+		pub max_transactions: u32,
+		/// Length of a rate-limit window, in blocks.
+		pub period: BlockNumber,
+		/// Block at which the current rate-limit window started.
+		pub period_start: BlockNumber,
+		/// Sponsored transactions in the current rate-limit window.
+		pub transactions: u32,
+		/// Deposit reserved for the registration.
+		pub deposit: Balance,
+	}
+
+	/// The sponsor of an account.
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub struct SponsoredBy<AccountId, Balance> {
+		/// The account paying the fees.
+		pub sponsor: AccountId,
+		/// Deposit the sponsor reserved for the account.
+		pub deposit: Balance,
+	}
+
+	/// Configure the pallet by specifying the parameters and types on which it depends.
+	#[pallet::config]
+	pub trait Config: frame_system::Config + pallet_transaction_payment::Config {
+		/// Because this pallet emits events, it depends on the runtime's definition of an event.
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// The currency deposits are reserved in, the one fees are charged in.
+		type Currency: ReservableCurrency<Self::AccountId, Balance = BalanceOf<Self>>;
+
+		/// The calls a sponsor may pay the fee for.
+		type SponsorableCall: Contains<<Self as frame_system::Config>::Call>;
+
+		/// The deposit reserved for registering as a sponsor.
+		#[pallet::constant]
+		type SponsorDeposit: Get<BalanceOf<Self>>;
+
+		/// The deposit reserved from a sponsor for every account it sponsors.
+		#[pallet::constant]
+		type SponsorshipDeposit: Get<BalanceOf<Self>>;
+
+		/// The least budget, and free balance, a sponsor needs to keep its accounts. Below it, or
+		/// with its rate limit used up, another sponsor may take them over.
+		#[pallet::constant]
+		type MinimumBudget: Get<BalanceOf<Self>>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	pub struct Pallet<T>(_);
+
+	/// Budget and rate limit of every registered sponsor.
+	#[pallet::storage]
+	#[pallet::getter(fn sponsors)]
+	pub type Sponsors<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, SponsorInfo<BalanceOf<T>, T::BlockNumber>>;
+
+	/// The sponsor paying the fees of an account.
+	#[pallet::storage]
+	#[pallet::getter(fn sponsor_of)]
+	pub type SponsorOf<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, SponsoredBy<T::AccountId, BalanceOf<T>>>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A sponsor registered or updated its budget and rate limit. [sponsor, budget]
+		SponsorRegistered(T::AccountId, BalanceOf<T>),
+		/// A sponsor stopped sponsoring. [sponsor]
+		SponsorRemoved(T::AccountId),
+		/// A sponsor started paying the fees of an account. [sponsor, who]
+		AccountSponsored(T::AccountId, T::AccountId),
+		/// A sponsor stopped paying the fees of an account. [sponsor, who]
+		SponsorshipRevoked(T::AccountId, T::AccountId),
+		/// An account stopped being sponsored. [sponsor, who]
+		SponsorshipLeft(T::AccountId, T::AccountId),
+		/// A sponsor paid the fee of a transaction. [sponsor, who, fee]
+		FeeSponsored(T::AccountId, T::AccountId, BalanceOf<T>),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The sender is not a registered sponsor.
+		NotSponsor,
+		/// The account is already sponsored by another sponsor that can still pay.
+		AlreadySponsored,
+		/// The account is not sponsored by the sender.
+		NotSponsoredBySender,
+		/// The sender is not sponsored.
+		NotSponsored,
+		/// The rate-limit period must be at least one block.
+		ZeroPeriod,
+		/// The budget is below `MinimumBudget` or no transaction is allowed per period.
+		NoAllowance,
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Register the sender as a sponsor, or update its budget and rate limit.
+		///
+		/// The sponsor pays for at most `max_transactions` transactions every `period` blocks and
+		/// for no more than `budget` in fees overall. Fees are withdrawn from the sponsor's free
+		/// balance as they are charged; the budget only caps them. A new sponsor reserves
+		/// `SponsorDeposit`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn register_sponsor(
+			origin: OriginFor<T>,
+			budget: BalanceOf<T>,
+			max_transactions: u32,
+			period: T::BlockNumber,
+		) -> DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+			ensure!(!period.is_zero(), Error::<T>::ZeroPeriod);
+			ensure!(
+				max_transactions > 0 && budget >= T::MinimumBudget::get(),
+				Error::<T>::NoAllowance
+			);
+
+			let deposit = match <Sponsors<T>>::get(&sponsor) {
+				Some(info) => info.deposit,
+				None => {
+					let deposit = T::SponsorDeposit::get();
+					T::Currency::reserve(&sponsor, deposit)?;
+					deposit
+				},
+			};
+			let info = SponsorInfo {
+				budget,
+				max_transactions,
+				period,
+				period_start: <frame_system::Pallet<T>>::block_number(),
+				transactions: 0,
+				deposit,
+			};
+			<Sponsors<T>>::insert(&sponsor, info);
+
+			Self::deposit_event(Event::SponsorRegistered(sponsor, budget));
+			Ok(())
+		}
+
+		/// Stop sponsoring. Accounts sponsored by the sender go back to paying their own fees;
+		/// their deposits stay reserved until they are revoked, left or taken over.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn unregister_sponsor(origin: OriginFor<T>) -> DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+			let info = <Sponsors<T>>::take(&sponsor).ok_or(Error::<T>::NotSponsor)?;
+
+			T::Currency::unreserve(&sponsor, info.deposit);
+
+			Self::deposit_event(Event::SponsorRemoved(sponsor));
+			Ok(())
+		}
+
+		/// Start paying the fees of `who`, reserving `SponsorshipDeposit`.
+		///
+		/// Fails if `who` is already sponsored by another sponsor that can still pay for it.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(5, 3))]
+		pub fn sponsor_account(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+			ensure!(<Sponsors<T>>::contains_key(&sponsor), Error::<T>::NotSponsor);
+
+			let deposit = T::SponsorshipDeposit::get();
+			match <SponsorOf<T>>::get(&who) {
+				Some(current) if current.sponsor == sponsor => return Ok(()),
+				Some(current) => {
+					ensure!(!Self::can_sponsor(&current.sponsor), Error::<T>::AlreadySponsored);
+					T::Currency::reserve(&sponsor, deposit)?;
+					T::Currency::unreserve(&current.sponsor, current.deposit);
+				},
+				None => T::Currency::reserve(&sponsor, deposit)?,
+			}
+			<SponsorOf<T>>::insert(&who, SponsoredBy { sponsor: sponsor.clone(), deposit });
+
+			Self::deposit_event(Event::AccountSponsored(sponsor, who));
+			Ok(())
+		}
+
+		/// Stop paying the fees of `who`.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn revoke_sponsorship(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			let sponsor = ensure_signed(origin)?;
+			let sponsored = <SponsorOf<T>>::get(&who)
+				.filter(|sponsored| sponsored.sponsor == sponsor)
+				.ok_or(Error::<T>::NotSponsoredBySender)?;
+
+			<SponsorOf<T>>::remove(&who);
+			T::Currency::unreserve(&sponsor, sponsored.deposit);
+
+			Self::deposit_event(Event::SponsorshipRevoked(sponsor, who));
+			Ok(())
+		}
+
+		/// Stop being sponsored and pay for the sender's own transactions again.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn leave_sponsorship(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let sponsored = <SponsorOf<T>>::take(&who).ok_or(Error::<T>::NotSponsored)?;
+
+			T::Currency::unreserve(&sponsored.sponsor, sponsored.deposit);
+
+			Self::deposit_event(Event::SponsorshipLeft(sponsored.sponsor, who));
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The sponsor that pays `fee` for `who` dispatching `call`, if any.
+		///
+		/// Returns `None` when the call is not sponsorable, `who` has no registered sponsor, or
+		/// the sponsor is out of budget or over its rate limit.
+		pub fn sponsor_for(
+			who: &T::AccountId,
+			call: &<T as frame_system::Config>::Call,
+			fee: BalanceOf<T>,
+		) -> Option<T::AccountId> {
+			if !T::SponsorableCall::contains(call) {
+				return None
+			}
+			let sponsor = <SponsorOf<T>>::get(who)?.sponsor;
+			let info = Self::current_window(<Sponsors<T>>::get(&sponsor)?);
+
+			(info.transactions < info.max_transactions && info.budget >= fee).then(|| sponsor)
+		}
+
+		/// Whether a registered sponsor is responsible for the fee of `who` dispatching `call`,
+		/// whether or not it can pay it.
+		pub fn is_sponsored(who: &T::AccountId, call: &<T as frame_system::Config>::Call) -> bool {
+			T::SponsorableCall::contains(call) &&
+				<SponsorOf<T>>::get(who)
+					.map_or(false, |sponsored| <Sponsors<T>>::contains_key(&sponsored.sponsor))
+		}
+
+		/// Whether `sponsor` is registered, has rate left and at least `MinimumBudget` of both
+		/// budget and free balance.
+		fn can_sponsor(sponsor: &T::AccountId) -> bool {
+			let minimum = T::MinimumBudget::get();
+			<Sponsors<T>>::get(sponsor).map(Self::current_window).map_or(false, |info| {
+				info.transactions < info.max_transactions &&
+					info.budget >= minimum &&
+					T::Currency::free_balance(sponsor) >= minimum
+			})
+		}
+
+		/// Count a sponsored transaction against `sponsor`, taking `fee` from its budget.
+		pub(crate) fn note_sponsored(sponsor: &T::AccountId, fee: BalanceOf<T>) {
+			<Sponsors<T>>::mutate(sponsor, |maybe_info| {
+				if let Some(info) = maybe_info {
+					*info = Self::current_window(info.clone());
+					info.transactions = info.transactions.saturating_add(1);
+					info.budget = info.budget.saturating_sub(fee);
+				}
+			});
+		}
+
+		/// Return the part of `fee` that was not charged to `sponsor`'s budget and report the
+		/// fee that was actually paid.
+		pub(crate) fn note_fee_paid(
+			sponsor: T::AccountId,
+			who: T::AccountId,
+			fee: BalanceOf<T>,
+			actual_fee: BalanceOf<T>,
+		) {
+			<Sponsors<T>>::mutate(&sponsor, |maybe_info| {
+				if let Some(info) = maybe_info {
+					info.budget = info.budget.saturating_add(fee.saturating_sub(actual_fee));
+				}
+			});
+
+			Self::deposit_event(Event::FeeSponsored(sponsor, who, actual_fee));
+		}
+
+		/// Start a new rate-limit window if the current one has elapsed.
+		fn current_window(
+			mut info: SponsorInfo<BalanceOf<T>, T::BlockNumber>,
+		) -> SponsorInfo<BalanceOf<T>, T::BlockNumber> {
+			let now = <frame_system::Pallet<T>>::block_number();
+			if now >= info.period_start.saturating_add(info.period) {
+				info.period_start = now;
+				info.transactions = 0;
+			}
+			info
+		}
+	}
+}
@@ -0,0 +1,119 @@
+use crate as pallet_sponsorship;
+use frame_support::traits::{ConstU16, ConstU64, ConstU8, Contains};
+use frame_system as system;
+use pallet_transaction_payment::CurrencyAdapter;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		TransactionPayment: pallet_transaction_payment::{Pallet, Storage},
+		Sponsorship: pallet_sponsorship::{Pallet, Call, Storage, Event<T>},
+	}
+);
+
+frame_support::parameter_types! {
+	// No base extrinsic weight, so fees are just `len + weight`.
+	pub BlockWeights: frame_system::limits::BlockWeights =
+		frame_system::limits::BlockWeights::simple_max(1024);
+}
+
+impl system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = BlockWeights;
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ConstU16<7007>;
+	type OnSetCode = ();
+	type MaxConsumers = frame_support::traits::ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU64<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+frame_support::parameter_types! {
+	pub const TransactionByteFee: u64 = 1;
+}
+
+impl pallet_transaction_payment::Config for Test {
+	type OnChargeTransaction = CurrencyAdapter<Balances, ()>;
+	type TransactionByteFee = TransactionByteFee;
+	type OperationalFeeMultiplier = ConstU8<5>;
+	type WeightToFee = frame_support::weights::IdentityFee<u64>;
+	type FeeMultiplierUpdate = ();
+}
+
+/// Only `System::remark` may be sponsored in tests.
+pub struct SponsorableCalls;
+impl Contains<Call> for SponsorableCalls {
+	fn contains(call: &Call) -> bool {
+		matches!(call, Call::System(frame_system::Call::remark { .. }))
+	}
+}
+
+pub const SPONSOR_DEPOSIT: u64 = 10;
+pub const SPONSORSHIP_DEPOSIT: u64 = 5;
+pub const MINIMUM_BUDGET: u64 = 100;
+
+impl pallet_sponsorship::Config for Test {
+	type Event = Event;
+	type Currency = Balances;
+	type SponsorableCall = SponsorableCalls;
+	type SponsorDeposit = ConstU64<SPONSOR_DEPOSIT>;
+	type SponsorshipDeposit = ConstU64<SPONSORSHIP_DEPOSIT>;
+	type MinimumBudget = ConstU64<MINIMUM_BUDGET>;
+}
+
+pub const SPONSOR: u64 = 1;
+pub const NEWCOMER: u64 = 2;
+pub const FUNDED: u64 = 3;
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> { balances: vec![(SPONSOR, 1_000), (FUNDED, 1_000)] }
+		.assimilate_storage(&mut t)
+		.unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
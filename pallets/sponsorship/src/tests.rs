@@ -0,0 +1,289 @@
+use crate::{
+	mock::*, ChargeSponsoredTransactionPayment, Error, Event as SponsorshipEvent, SponsorInfo,
+};
+use frame_support::{
+	assert_noop, assert_ok,
+	weights::{DispatchInfo, PostDispatchInfo},
+};
+use pallet_transaction_payment::ChargeTransactionPayment;
+use sp_runtime::{
+	traits::SignedExtension,
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+};
+
+type Extension = ChargeSponsoredTransactionPayment<Test, ChargeTransactionPayment<Test>>;
+
+const LEN: usize = 10;
+// `LEN` bytes plus a weight of 100, both at one unit each.
+const FEE: u64 = 110;
+
+fn extension() -> Extension {
+	ChargeTransactionPayment::<Test>::from(0).into()
+}
+
+fn info() -> DispatchInfo {
+	DispatchInfo { weight: 100, ..Default::default() }
+}
+
+fn remark() -> Call {
+	Call::System(frame_system::Call::remark { remark: vec![] })
+}
+
+fn remark_with_event() -> Call {
+	Call::System(frame_system::Call::remark_with_event { remark: vec![] })
+}
+
+/// Run a transaction of `who` through the extension, the way the executive would.
+fn charge(who: u64, call: &Call) -> Result<(), TransactionValidityError> {
+	extension().validate(&who, call, &info(), LEN)?;
+	let pre = extension().pre_dispatch(&who, call, &info(), LEN)?;
+	Extension::post_dispatch(pre, &info(), &PostDispatchInfo::default(), LEN, &Ok(()))
+}
+
+fn sponsor_newcomer(budget: u64, max_transactions: u32, period: u64) {
+	assert_ok!(Sponsorship::register_sponsor(
+		Origin::signed(SPONSOR),
+		budget,
+		max_transactions,
+		period
+	));
+	assert_ok!(Sponsorship::sponsor_account(Origin::signed(SPONSOR), NEWCOMER));
+}
+
+#[test]
+fn register_sponsor_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Sponsorship::register_sponsor(Origin::signed(SPONSOR), 500, 3, 10));
+		assert_eq!(
+			Sponsorship::sponsors(SPONSOR),
+			Some(SponsorInfo {
+				budget: 500,
+				max_transactions: 3,
+				period: 10,
+				period_start: 1,
+				transactions: 0,
+				deposit: SPONSOR_DEPOSIT,
+			})
+		);
+		System::assert_last_event(SponsorshipEvent::SponsorRegistered(SPONSOR, 500).into());
+
+		// Updating the registration reserves nothing more.
+		assert_ok!(Sponsorship::register_sponsor(Origin::signed(SPONSOR), 600, 3, 10));
+		assert_eq!(Balances::reserved_balance(SPONSOR), SPONSOR_DEPOSIT);
+	});
+}
+
+#[test]
+fn register_sponsor_requires_an_allowance() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Sponsorship::register_sponsor(Origin::signed(SPONSOR), MINIMUM_BUDGET - 1, 3, 10),
+			Error::<Test>::NoAllowance
+		);
+		assert_noop!(
+			Sponsorship::register_sponsor(Origin::signed(SPONSOR), 500, 0, 10),
+			Error::<Test>::NoAllowance
+		);
+	});
+}
+
+#[test]
+fn deposits_are_reserved_and_returned() {
+	new_test_ext().execute_with(|| {
+		sponsor_newcomer(500, 3, 10);
+		assert_eq!(Balances::reserved_balance(SPONSOR), SPONSOR_DEPOSIT + SPONSORSHIP_DEPOSIT);
+
+		assert_ok!(Sponsorship::revoke_sponsorship(Origin::signed(SPONSOR), NEWCOMER));
+		assert_eq!(Balances::reserved_balance(SPONSOR), SPONSOR_DEPOSIT);
+
+		assert_ok!(Sponsorship::unregister_sponsor(Origin::signed(SPONSOR)));
+		assert_eq!(Balances::reserved_balance(SPONSOR), 0);
+		assert_eq!(Balances::free_balance(SPONSOR), 1_000);
+	});
+}
+
+#[test]
+fn register_sponsor_rejects_zero_period() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Sponsorship::register_sponsor(Origin::signed(SPONSOR), 500, 3, 0),
+			Error::<Test>::ZeroPeriod
+		);
+	});
+}
+
+#[test]
+fn only_sponsors_can_sponsor_accounts() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Sponsorship::sponsor_account(Origin::signed(SPONSOR), NEWCOMER),
+			Error::<Test>::NotSponsor
+		);
+	});
+}
+
+#[test]
+fn sponsored_account_cannot_be_taken_over() {
+	new_test_ext().execute_with(|| {
+		sponsor_newcomer(500, 3, 10);
+		assert_ok!(Sponsorship::register_sponsor(Origin::signed(FUNDED), 500, 3, 10));
+		assert_noop!(
+			Sponsorship::sponsor_account(Origin::signed(FUNDED), NEWCOMER),
+			Error::<Test>::AlreadySponsored
+		);
+
+		// Once the first sponsor is gone the account is up for grabs.
+		assert_ok!(Sponsorship::unregister_sponsor(Origin::signed(SPONSOR)));
+		assert_ok!(Sponsorship::sponsor_account(Origin::signed(FUNDED), NEWCOMER));
+		assert_eq!(
+			Sponsorship::sponsor_of(NEWCOMER).map(|sponsored| sponsored.sponsor),
+			Some(FUNDED)
+		);
+		assert_eq!(Balances::reserved_balance(SPONSOR), 0);
+		assert_eq!(Balances::reserved_balance(FUNDED), SPONSOR_DEPOSIT + SPONSORSHIP_DEPOSIT);
+	});
+}
+
+#[test]
+fn sponsor_without_rate_left_can_be_taken_over() {
+	new_test_ext().execute_with(|| {
+		sponsor_newcomer(500, 1, 10);
+		assert_ok!(Sponsorship::register_sponsor(Origin::signed(FUNDED), 500, 3, 10));
+
+		assert_ok!(charge(NEWCOMER, &remark()));
+		assert_ok!(Sponsorship::sponsor_account(Origin::signed(FUNDED), NEWCOMER));
+
+		assert_eq!(
+			Sponsorship::sponsor_of(NEWCOMER).map(|sponsored| sponsored.sponsor),
+			Some(FUNDED)
+		);
+		assert_eq!(Balances::reserved_balance(SPONSOR), SPONSOR_DEPOSIT);
+	});
+}
+
+#[test]
+fn unfunded_sponsor_can_be_taken_over() {
+	new_test_ext().execute_with(|| {
+		sponsor_newcomer(500, 3, 10);
+		assert_ok!(Sponsorship::register_sponsor(Origin::signed(FUNDED), 500, 3, 10));
+
+		// The budget is only a cap, a sponsor without the funds behind it pays for nothing.
+		assert_ok!(Balances::transfer(Origin::signed(SPONSOR), FUNDED, 900));
+		assert_ok!(Sponsorship::sponsor_account(Origin::signed(FUNDED), NEWCOMER));
+
+		assert_eq!(
+			Sponsorship::sponsor_of(NEWCOMER).map(|sponsored| sponsored.sponsor),
+			Some(FUNDED)
+		);
+	});
+}
+
+#[test]
+fn sponsored_accounts_can_leave() {
+	new_test_ext().execute_with(|| {
+		sponsor_newcomer(500, 3, 10);
+
+		assert_ok!(Sponsorship::leave_sponsorship(Origin::signed(NEWCOMER)));
+
+		assert_eq!(Sponsorship::sponsor_of(NEWCOMER), None);
+		assert_eq!(Balances::reserved_balance(SPONSOR), SPONSOR_DEPOSIT);
+		System::assert_last_event(SponsorshipEvent::SponsorshipLeft(SPONSOR, NEWCOMER).into());
+		assert_noop!(
+			Sponsorship::leave_sponsorship(Origin::signed(NEWCOMER)),
+			Error::<Test>::NotSponsored
+		);
+	});
+}
+
+#[test]
+fn only_the_sponsor_can_revoke() {
+	new_test_ext().execute_with(|| {
+		sponsor_newcomer(500, 3, 10);
+		assert_noop!(
+			Sponsorship::revoke_sponsorship(Origin::signed(FUNDED), NEWCOMER),
+			Error::<Test>::NotSponsoredBySender
+		);
+		assert_ok!(Sponsorship::revoke_sponsorship(Origin::signed(SPONSOR), NEWCOMER));
+		assert_eq!(Sponsorship::sponsor_of(NEWCOMER), None);
+	});
+}
+
+#[test]
+fn sponsor_pays_the_fee() {
+	new_test_ext().execute_with(|| {
+		sponsor_newcomer(500, 3, 10);
+
+		assert_ok!(charge(NEWCOMER, &remark()));
+
+		assert_eq!(
+			Balances::free_balance(SPONSOR),
+			1_000 - SPONSOR_DEPOSIT - SPONSORSHIP_DEPOSIT - FEE
+		);
+		assert_eq!(Balances::free_balance(NEWCOMER), 0);
+		let info = Sponsorship::sponsors(SPONSOR).unwrap();
+		assert_eq!(info.budget, 500 - FEE);
+		assert_eq!(info.transactions, 1);
+		System::assert_last_event(SponsorshipEvent::FeeSponsored(SPONSOR, NEWCOMER, FEE).into());
+	});
+}
+
+#[test]
+fn other_calls_are_paid_by_the_signer() {
+	new_test_ext().execute_with(|| {
+		sponsor_newcomer(500, 3, 10);
+
+		assert_eq!(
+			charge(NEWCOMER, &remark_with_event()),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Payment))
+		);
+		assert_ok!(charge(FUNDED, &remark()));
+
+		assert_eq!(Balances::free_balance(SPONSOR), 1_000 - SPONSOR_DEPOSIT - SPONSORSHIP_DEPOSIT);
+		assert_eq!(Balances::free_balance(FUNDED), 1_000 - FEE);
+	});
+}
+
+#[test]
+fn rate_limit_resets_every_period() {
+	new_test_ext().execute_with(|| {
+		sponsor_newcomer(500, 1, 10);
+
+		assert_ok!(charge(NEWCOMER, &remark()));
+		assert_eq!(
+			charge(NEWCOMER, &remark()),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Payment))
+		);
+
+		System::set_block_number(11);
+		assert_ok!(charge(NEWCOMER, &remark()));
+		assert_eq!(
+			Balances::free_balance(SPONSOR),
+			1_000 - SPONSOR_DEPOSIT - SPONSORSHIP_DEPOSIT - 2 * FEE
+		);
+	});
+}
+
+#[test]
+fn exhausted_budget_is_not_charged_to_the_signer() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Sponsorship::register_sponsor(Origin::signed(SPONSOR), FEE + 1, 3, 10));
+		assert_ok!(Sponsorship::sponsor_account(Origin::signed(SPONSOR), FUNDED));
+
+		assert_ok!(charge(FUNDED, &remark()));
+		assert_eq!(
+			charge(FUNDED, &remark()),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Payment))
+		);
+		assert_eq!(
+			extension().pre_dispatch(&FUNDED, &remark(), &info(), LEN).err(),
+			Some(TransactionValidityError::Invalid(InvalidTransaction::Payment))
+		);
+		assert_eq!(Sponsorship::sponsors(SPONSOR).unwrap().budget, 1);
+		assert_eq!(Balances::free_balance(FUNDED), 1_000);
+
+		// Once it leaves its sponsor the signer pays for itself.
+		assert_ok!(Sponsorship::leave_sponsorship(Origin::signed(FUNDED)));
+		assert_ok!(charge(FUNDED, &remark()));
+		assert_eq!(Balances::free_balance(FUNDED), 1_000 - FEE);
+	});
+}
@@ -0,0 +1,193 @@
+//! Previews of what an extrinsic would do, for wallets.
+
+use crate::{AllPalletsWithSystem, Block, Event, Executive, System};
+use codec::{Decode, Encode};
+use frame_support::{traits::PalletsInfoAccess, weights::Weight};
+use sp_runtime::{traits::Block as BlockT, ApplyExtrinsicResult, DispatchError, RuntimeDebug};
+use sp_std::prelude::*;
+
+/// What applying an extrinsic on top of a block's state did.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct DryRunResult<Event> {
+	/// Whether the extrinsic is valid, and if so its dispatch result.
+	pub result: ApplyExtrinsicResult,
+	/// Pallet and error name of the module error a failed dispatch returned.
+	pub module_error: Option<(Vec<u8>, Vec<u8>)>,
+	/// Events deposited while applying the extrinsic, fee payment included.
+	pub events: Vec<Event>,
+	/// Weight the extrinsic used.
+	pub weight: Weight,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Previews of an extrinsic's outcome.
+	pub trait DryRunApi<Event: codec::Codec> {
+		/// Apply `extrinsic` in the block being built on top of the block's state and report what
+		/// happened. Call `Core::initialize_block` with the child header first, as for
+		/// `BlockBuilder::apply_extrinsic`. Like every runtime API call, the state changes are
+		/// discarded afterwards.
+		fn dry_run(extrinsic: <Block as BlockT>::Extrinsic) -> DryRunResult<Event>;
+	}
+}
+
+/// Apply `extrinsic` to the current state, that of an initialized block. Only meant for runtime
+/// API calls, whose state changes are thrown away.
+pub fn dry_run(extrinsic: <Block as BlockT>::Extrinsic) -> DryRunResult<Event> {
+	let events_before = System::events().len();
+	let weight_before = System::block_weight().total();
+
+	let result = Executive::apply_extrinsic(extrinsic);
+
+	let weight = System::block_weight().total().saturating_sub(weight_before);
+	let events = System::events()
+		.into_iter()
+		.skip(events_before)
+		.map(|record| record.event)
+		.collect();
+	let module_error = match &result {
+		Ok(Err(DispatchError::Module(error))) => AllPalletsWithSystem::infos()
+			.into_iter()
+			.find(|pallet| pallet.index == error.index as usize)
+			.map(|pallet| {
+				(
+					pallet.name.as_bytes().to_vec(),
+					error.message.unwrap_or_default().as_bytes().to_vec(),
+				)
+			}),
+		_ => None,
+	};
+
+	DryRunResult { result, module_error, events, weight }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		impls::test_session_config, AccountId, BalancesCall, BalancesConfig, BuildStorage, Call,
+		GenesisConfig, Hash, Header, SignedExtra, UncheckedExtrinsic, UNIT, VERSION,
+	};
+	use sp_core::{sr25519, Pair};
+	use sp_runtime::{
+		generic::{Era, SignedPayload},
+		traits::Header as HeaderT,
+	};
+
+	/// Sign `call` the way a wallet does, with `era` checkpointed at the block hashed `era_hash`.
+	fn sign(pair: &sr25519::Pair, call: Call, era: Era, era_hash: Hash) -> UncheckedExtrinsic {
+		let extra: SignedExtra = (
+			frame_system::CheckNonZeroSender::new(),
+			frame_system::CheckSpecVersion::new(),
+			frame_system::CheckTxVersion::new(),
+			frame_system::CheckGenesis::new(),
+			frame_system::CheckEra::from(era),
+			frame_system::CheckNonce::from(0),
+			frame_system::CheckWeight::new(),
+			pallet_asset_tx_payment::ChargeAssetTxPayment::from(0, None).into(),
+		);
+		let additional = (
+			(),
+			VERSION.spec_version,
+			VERSION.transaction_version,
+			System::block_hash(0),
+			era_hash,
+			(),
+			(),
+			(),
+		);
+		let payload = SignedPayload::from_raw(call, extra, additional);
+		let signature = payload.using_encoded(|payload| pair.sign(payload));
+		let (call, extra, _) = payload.deconstruct();
+		UncheckedExtrinsic::new_signed(
+			call,
+			AccountId::from(pair.public()).into(),
+			signature.into(),
+			extra,
+		)
+	}
+
+	fn new_test_ext(balances: Vec<(AccountId, u128)>) -> sp_io::TestExternalities {
+		GenesisConfig {
+			balances: BalancesConfig { balances },
+			session: test_session_config(&[([1; 32], [1; 32])]),
+			..Default::default()
+		}
+		.build_storage()
+		.unwrap()
+		.into()
+	}
+
+	/// Import block 1 as the best block and initialize block 2 on top of it, as the dry-run RPC
+	/// does. Returns the hash of block 1.
+	fn build_on_best_block() -> Hash {
+		System::initialize(&1, &System::block_hash(0), &Default::default());
+		let best = System::finalize().hash();
+		Executive::initialize_block(&Header::new(
+			2,
+			Default::default(),
+			Default::default(),
+			best,
+			Default::default(),
+		));
+		best
+	}
+
+	#[test]
+	fn dry_run_reports_module_errors_events_and_weight() {
+		let pair = sr25519::Pair::from_seed(&[7; 32]);
+		new_test_ext(vec![(AccountId::from(pair.public()), UNIT)]).execute_with(|| {
+			build_on_best_block();
+			let transfer = Call::Balances(BalancesCall::transfer {
+				dest: AccountId::from([9; 32]).into(),
+				value: 2 * UNIT,
+			});
+
+			let outcome = dry_run(sign(&pair, transfer, Era::Immortal, System::block_hash(0)));
+
+			assert!(matches!(outcome.result, Ok(Err(DispatchError::Module(_)))));
+			assert_eq!(
+				outcome.module_error,
+				Some((b"Balances".to_vec(), b"InsufficientBalance".to_vec()))
+			);
+			assert!(matches!(
+				outcome.events.last(),
+				Some(Event::System(frame_system::Event::ExtrinsicFailed { .. }))
+			));
+			assert!(outcome.weight > 0);
+		});
+	}
+
+	#[test]
+	fn dry_run_accepts_extrinsics_mortal_from_the_best_block() {
+		let pair = sr25519::Pair::from_seed(&[7; 32]);
+		new_test_ext(vec![(AccountId::from(pair.public()), UNIT)]).execute_with(|| {
+			let best = build_on_best_block();
+			let remark = Call::System(frame_system::Call::remark { remark: vec![] });
+
+			// Wallets sign mortal extrinsics whose era starts at the best block.
+			let outcome = dry_run(sign(&pair, remark, Era::mortal(64, 1), best));
+
+			assert_eq!(outcome.result, Ok(Ok(())));
+			assert!(matches!(
+				outcome.events.last(),
+				Some(Event::System(frame_system::Event::ExtrinsicSuccess { .. }))
+			));
+		});
+	}
+
+	#[test]
+	fn dry_run_reports_invalid_extrinsics() {
+		let pair = sr25519::Pair::from_seed(&[7; 32]);
+		new_test_ext(vec![]).execute_with(|| {
+			build_on_best_block();
+			let remark = Call::System(frame_system::Call::remark { remark: vec![] });
+
+			// The signer has no funds to pay the fee with.
+			let outcome = dry_run(sign(&pair, remark, Era::Immortal, System::block_hash(0)));
+
+			assert!(outcome.result.is_err());
+			assert_eq!(outcome.module_error, None);
+			assert!(outcome.events.is_empty());
+		});
+	}
+}
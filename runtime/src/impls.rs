@@ -0,0 +1,604 @@
+//! Some configurable implementations as associated type for the node template runtime.
+
+use crate::{
+	AccountId, AdjustmentVariable, Assets, Authorship, Balances, Call, Identity,
+	KeptHistoricalSessions, MinimumMultiplier, Runtime, Session, TargetBlockFullness, Treasury,
+};
+use frame_support::traits::{
+	fungibles::{Balanced, CreditOf},
+	Contains, Currency, Imbalance, OnUnbalanced,
+};
+use pallet_asset_tx_payment::HandleCredit;
+use pallet_identity::Judgement;
+use pallet_transaction_payment::TargetedFeeAdjustment;
+
+/// The validator account of the author of the current block.
+///
+/// The author index in Aura's pre-runtime digest is mapped to the session's validators, so fees
+/// keep going to the validator after it rotates its session keys.
+pub fn block_author() -> Option<AccountId> {
+	Authorship::author()
+}
+
+type NegativeImbalance = <Balances as Currency<AccountId>>::NegativeImbalance;
+
+/// Pays an imbalance to the block author, or to the treasury when the author is unknown.
+pub struct ToBlockAuthor;
+impl OnUnbalanced<NegativeImbalance> for ToBlockAuthor {
+	fn on_nonzero_unbalanced(amount: NegativeImbalance) {
+		match block_author() {
+			Some(author) => Balances::resolve_creating(&author, amount),
+			None => Treasury::on_unbalanced(amount),
+		}
+	}
+}
+
+/// Splits transaction fees between the treasury and the block author.
+pub struct DealWithFees;
+impl OnUnbalanced<NegativeImbalance> for DealWithFees {
+	fn on_unbalanceds<B>(mut fees_then_tips: impl Iterator<Item = NegativeImbalance>) {
+		if let Some(fees) = fees_then_tips.next() {
+			// for fees, 80% to treasury, 20% to author
+			let mut split = fees.ration(80, 20);
+			if let Some(tips) = fees_then_tips.next() {
+				// for tips, if any, 100% to author
+				tips.merge_into(&mut split.1);
+			}
+			Treasury::on_unbalanced(split.0);
+			ToBlockAuthor::on_unbalanced(split.1);
+		}
+	}
+}
+
+/// Adjusts the fee multiplier after every block, raising fees while blocks are fuller than
+/// `TargetBlockFullness` and lowering them, down to `MinimumMultiplier`, while they are emptier.
+pub type CongestionFeeUpdate<R> =
+	TargetedFeeAdjustment<R, TargetBlockFullness, AdjustmentVariable, MinimumMultiplier>;
+
+/// A `HandleCredit` implementation that transfers fees paid in assets to the block author, or
+/// to the treasury when there is no author, like [`ToBlockAuthor`] does for native fees.
+pub struct CreditToBlockAuthor;
+impl HandleCredit<AccountId, Assets> for CreditToBlockAuthor {
+	fn handle_credit(credit: CreditOf<AccountId, Assets>) {
+		let receiver = block_author().unwrap_or_else(Treasury::account_id);
+		// Drop the result which will trigger the `OnDrop` of the imbalance in case of error.
+		let _ = Assets::resolve(&receiver, credit);
+	}
+}
+
+/// The calls a `pallet_sponsorship` sponsor may pay the fee for.
+///
+/// The NFT `mint` and marketplace `create_sale` calls belong here once those pallets are part of
+/// the runtime; until then only the template pallet's calls can be sponsored.
+pub struct SponsorableCalls;
+impl Contains<Call> for SponsorableCalls {
+	fn contains(call: &Call) -> bool {
+		matches!(call, Call::TemplateModule(..))
+	}
+}
+
+/// Creators whose on-chain identity a registrar judged `Reasonable` or `KnownGood`.
+///
+/// The NFT pallet will read this at mint time to set a token's `verified_creator` flag.
+pub struct VerifiedCreators;
+impl Contains<AccountId> for VerifiedCreators {
+	fn contains(who: &AccountId) -> bool {
+		Identity::identity(who).map_or(false, |registration| {
+			registration.judgements.iter().any(|(_, judgement)| {
+				matches!(judgement, Judgement::Reasonable | Judgement::KnownGood)
+			})
+		})
+	}
+}
+
+/// The validator set of every session: the owners of the genesis session keys.
+///
+/// Nothing elects validators on this chain, they only rotate their keys. The set is handed to
+/// `pallet_session` again when a validator rotated its keys, so `pallet_session_historical` notes
+/// a root proving ownership of the new keys, which GRANDPA equivocation reports rely on. Otherwise
+/// the set is unchanged and the previous session's root is kept. Roots older than
+/// `KeptHistoricalSessions` are pruned.
+pub struct GenesisValidators;
+impl pallet_session::historical::SessionManager<AccountId, AccountId> for GenesisValidators {
+	fn new_session(index: u32) -> Option<Vec<(AccountId, AccountId)>> {
+		// Equivocations from before then can no longer be reported.
+		if let Some(oldest) = index.checked_sub(KeptHistoricalSessions::get()) {
+			pallet_session::historical::Pallet::<Runtime>::prune_up_to(oldest);
+		}
+
+		// The queued keys are the ones of the session starting now.
+		let rotated = Session::queued_keys().into_iter().any(|(validator, keys)| {
+			pallet_session::NextKeys::<Runtime>::get(&validator) != Some(keys)
+		});
+		rotated.then(|| Session::validators().into_iter().map(|v| (v.clone(), v)).collect())
+	}
+
+	fn new_session_genesis(_: u32) -> Option<Vec<(AccountId, AccountId)>> {
+		// `pallet_session` sets the genesis keys before asking for the first validators.
+		let mut validators: Vec<_> = pallet_session::NextKeys::<Runtime>::iter_keys().collect();
+		validators.sort();
+		Some(validators.into_iter().map(|v| (v.clone(), v)).collect())
+	}
+
+	fn end_session(_: u32) {}
+
+	fn start_session(_: u32) {}
+}
+
+/// Genesis session keys for runtime tests: `(account and Aura key, GRANDPA key)` per validator.
+#[cfg(test)]
+pub(crate) fn test_session_config(validators: &[([u8; 32], [u8; 32])]) -> crate::SessionConfig {
+	use sp_core::{ed25519, sr25519};
+
+	let keys = validators
+		.iter()
+		.map(|(account, grandpa)| {
+			let keys = crate::opaque::SessionKeys {
+				aura: sr25519::Public::from_raw(*account).into(),
+				grandpa: ed25519::Public::from_raw(*grandpa).into(),
+			};
+			(AccountId::from(*account), AccountId::from(*account), keys)
+		})
+		.collect();
+	crate::SessionConfig { keys }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		opaque::SessionKeys, AssetId, Aura, BalancesConfig, BlockWeights, BuildStorage, Event,
+		GenesisConfig, Grandpa, Historical, Origin, System, UNIT,
+	};
+	use codec::Encode;
+	use frame_support::{
+		assert_err, assert_ok,
+		traits::{ExistenceRequirement, Get, KeyOwnerProofSystem, WithdrawReasons},
+		weights::{DispatchClass, Weight},
+	};
+	use pallet_grandpa::{fg_primitives, AuthorityId as GrandpaId};
+	use pallet_identity::{Data, IdentityInfo};
+	use pallet_transaction_payment::Multiplier;
+	use sp_consensus_aura::{Slot, AURA_ENGINE_ID};
+	use sp_core::H256;
+	use sp_keyring::{Ed25519Keyring, Sr25519Keyring};
+	use sp_runtime::{traits::Convert, DigestItem, FixedPointNumber};
+
+	const AUTHOR: [u8; 32] = [1; 32];
+	const PAYER: [u8; 32] = [2; 32];
+	const CREATOR: [u8; 32] = [3; 32];
+	const ASSET: AssetId = 7;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		GenesisConfig {
+			session: test_session_config(&[(AUTHOR, AUTHOR)]),
+			balances: BalancesConfig {
+				balances: vec![
+					(AccountId::from(PAYER), 1_000_000),
+					(AccountId::from(CREATOR), 100 * UNIT),
+				],
+			},
+			..Default::default()
+		}
+		.build_storage()
+		.unwrap()
+		.into()
+	}
+
+	fn author_the_block() {
+		System::deposit_log(DigestItem::PreRuntime(AURA_ENGINE_ID, Slot::from(0).encode()));
+	}
+
+	fn withdraw(amount: u128) -> NegativeImbalance {
+		Balances::withdraw(
+			&AccountId::from(PAYER),
+			amount,
+			WithdrawReasons::FEE,
+			ExistenceRequirement::KeepAlive,
+		)
+		.unwrap()
+	}
+
+	#[test]
+	fn block_author_is_resolved_from_aura_digest() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(block_author(), None);
+			author_the_block();
+			assert_eq!(block_author(), Some(AccountId::from(AUTHOR)));
+		});
+	}
+
+	#[test]
+	fn block_author_stays_the_validator_after_key_rotation() {
+		new_test_ext().execute_with(|| {
+			let validator = AccountId::from(AUTHOR);
+			let keys = SessionKeys {
+				aura: sp_core::sr25519::Public::from_raw([9; 32]).into(),
+				grandpa: sp_core::ed25519::Public::from_raw([9; 32]).into(),
+			};
+			assert_ok!(Session::set_keys(Origin::signed(validator.clone()), keys.clone(), vec![]));
+			// New keys are queued by the next session and used from the one after.
+			Session::rotate_session();
+			Session::rotate_session();
+			assert_eq!(Aura::authorities().into_inner(), vec![keys.aura]);
+
+			author_the_block();
+			assert_eq!(block_author(), Some(validator));
+		});
+	}
+
+	#[test]
+	fn fees_are_split_between_treasury_and_author() {
+		new_test_ext().execute_with(|| {
+			author_the_block();
+			let issuance = Balances::total_issuance();
+			let treasury = Balances::free_balance(Treasury::account_id());
+
+			DealWithFees::on_unbalanceds(vec![withdraw(10_000), withdraw(1_000)].into_iter());
+
+			assert_eq!(Balances::free_balance(Treasury::account_id()), treasury + 8_000);
+			assert_eq!(Balances::free_balance(AccountId::from(AUTHOR)), 2_000 + 1_000);
+			assert_eq!(Balances::total_issuance(), issuance);
+		});
+	}
+
+	#[test]
+	fn author_share_goes_to_treasury_without_author() {
+		new_test_ext().execute_with(|| {
+			let issuance = Balances::total_issuance();
+			let treasury = Balances::free_balance(Treasury::account_id());
+
+			DealWithFees::on_unbalanceds(vec![withdraw(10_000), withdraw(1_000)].into_iter());
+
+			assert_eq!(Balances::free_balance(Treasury::account_id()), treasury + 11_000);
+			assert_eq!(Balances::total_issuance(), issuance);
+		});
+	}
+
+	/// Withdraw `amount` of a fresh asset from the payer, as an asset fee payment would.
+	fn withdraw_asset(amount: u128) -> CreditOf<AccountId, Assets> {
+		let payer = AccountId::from(PAYER);
+		assert_ok!(Assets::force_create(Origin::root(), ASSET, payer.clone().into(), true, 1));
+		assert_ok!(Assets::mint(
+			Origin::signed(payer.clone()),
+			ASSET,
+			payer.clone().into(),
+			10_000
+		));
+		<Assets as Balanced<_>>::withdraw(ASSET, &payer, amount).unwrap()
+	}
+
+	#[test]
+	fn asset_fees_go_to_author() {
+		new_test_ext().execute_with(|| {
+			author_the_block();
+			CreditToBlockAuthor::handle_credit(withdraw_asset(300));
+
+			assert_eq!(Assets::balance(ASSET, AccountId::from(AUTHOR)), 300);
+			assert_eq!(Assets::balance(ASSET, AccountId::from(PAYER)), 9_700);
+		});
+	}
+
+	#[test]
+	fn asset_fees_go_to_treasury_without_author() {
+		new_test_ext().execute_with(|| {
+			CreditToBlockAuthor::handle_credit(withdraw_asset(300));
+
+			assert_eq!(Assets::balance(ASSET, Treasury::account_id()), 300);
+			assert_eq!(Assets::total_supply(ASSET), 10_000);
+		});
+	}
+
+	#[test]
+	fn dust_goes_to_treasury() {
+		new_test_ext().execute_with(|| {
+			let issuance = Balances::total_issuance();
+			let treasury = Balances::free_balance(Treasury::account_id());
+			let payer = AccountId::from(PAYER);
+			let receiver = AccountId::from(AUTHOR);
+
+			// Leaves 100 behind, below the existential deposit of 500.
+			assert!(<Balances as Currency<_>>::transfer(
+				&payer,
+				&receiver,
+				999_900,
+				ExistenceRequirement::AllowDeath,
+			)
+			.is_ok());
+
+			assert_eq!(Balances::free_balance(&payer), 0);
+			assert_eq!(Balances::free_balance(Treasury::account_id()), treasury + 100);
+			assert_eq!(Balances::total_issuance(), issuance);
+		});
+	}
+
+	fn max_normal() -> Weight {
+		let weights = BlockWeights::get();
+		weights.get(DispatchClass::Normal).max_total.unwrap_or(weights.max_block)
+	}
+
+	fn target() -> Weight {
+		TargetBlockFullness::get() * max_normal()
+	}
+
+	/// Feed `blocks` blocks of `weight` through the fee update, returning every multiplier.
+	fn trajectory(start: Multiplier, weight: Weight, blocks: usize) -> Vec<Multiplier> {
+		new_test_ext().execute_with(|| {
+			let mut multiplier = start;
+			(0..blocks)
+				.map(|_| {
+					System::set_block_consumed_resources(weight, 0);
+					multiplier = CongestionFeeUpdate::<Runtime>::convert(multiplier);
+					multiplier
+				})
+				.collect()
+		})
+	}
+
+	/// The per-block factor `TargetedFeeAdjustment` applies for a block of `weight`.
+	fn expected_factor(weight: Weight) -> f64 {
+		let v = AdjustmentVariable::get().to_float();
+		let diff = (weight as f64 - target() as f64) / max_normal() as f64;
+		1.0 + v * diff + (v * diff).powi(2) / 2.0
+	}
+
+	fn assert_close(actual: Multiplier, expected: f64) {
+		let actual = actual.to_float();
+		assert!((actual - expected).abs() <= expected * 1e-6, "{} != {}", actual, expected);
+	}
+
+	#[test]
+	fn multiplier_is_stable_at_target_fullness() {
+		let multipliers = trajectory(Multiplier::saturating_from_integer(2), target(), 10);
+		assert!(multipliers.iter().all(|m| *m == Multiplier::saturating_from_integer(2)));
+	}
+
+	#[test]
+	fn full_blocks_raise_the_multiplier() {
+		let multipliers = trajectory(Multiplier::saturating_from_integer(1), max_normal(), 600);
+
+		let mut expected = 1.0;
+		for multiplier in &multipliers {
+			expected *= expected_factor(max_normal());
+			assert_close(*multiplier, expected);
+		}
+		// An hour of full blocks makes fees about 57% more expensive.
+		assert!(multipliers[599] > Multiplier::saturating_from_rational(156, 100));
+		assert!(multipliers[599] < Multiplier::saturating_from_rational(158, 100));
+	}
+
+	#[test]
+	fn empty_blocks_lower_the_multiplier_to_the_minimum() {
+		let start = Multiplier::saturating_from_integer(2);
+		let multipliers = trajectory(start, 0, 6_000);
+
+		let mut expected = 2.0;
+		for window in multipliers.windows(2).take(100) {
+			assert!(window[1] < window[0]);
+		}
+		for multiplier in multipliers.iter().take(100) {
+			expected *= expected_factor(0);
+			assert_close(*multiplier, expected);
+		}
+		assert!(multipliers.iter().all(|m| *m >= MinimumMultiplier::get()));
+		assert_eq!(multipliers.last(), Some(&MinimumMultiplier::get()));
+	}
+
+	#[test]
+	fn full_then_empty_blocks_return_to_the_minimum() {
+		let rising = trajectory(Multiplier::saturating_from_integer(1), max_normal(), 100);
+		let peak = *rising.last().unwrap();
+		assert!(rising.windows(2).all(|w| w[1] > w[0]));
+
+		let falling = trajectory(peak, 0, 1_000);
+		assert!(falling.windows(2).all(|w| w[1] <= w[0]));
+		assert_eq!(falling.last(), Some(&Multiplier::saturating_from_integer(1)));
+	}
+
+	#[test]
+	fn creators_are_verified_by_registrar_judgement() {
+		new_test_ext().execute_with(|| {
+			let registrar = AccountId::from(AUTHOR);
+			let creator = AccountId::from(CREATOR);
+			let judge = |judgement| {
+				assert_ok!(Identity::provide_judgement(
+					Origin::signed(registrar.clone()),
+					0,
+					creator.clone().into(),
+					judgement,
+				));
+			};
+			assert!(!VerifiedCreators::contains(&creator));
+
+			assert_ok!(Identity::add_registrar(Origin::root(), registrar.clone()));
+			assert_ok!(Identity::set_identity(
+				Origin::signed(creator.clone()),
+				Box::new(IdentityInfo {
+					display: Data::Raw(b"creator".to_vec().try_into().unwrap()),
+					..Default::default()
+				}),
+			));
+			assert_ok!(Identity::request_judgement(Origin::signed(creator.clone()), 0, 0));
+			assert!(!VerifiedCreators::contains(&creator));
+
+			judge(Judgement::Reasonable);
+			assert!(VerifiedCreators::contains(&creator));
+
+			judge(Judgement::LowQuality);
+			assert!(!VerifiedCreators::contains(&creator));
+		});
+	}
+
+	/// Alice and Bob validate, with their keyring keys as session keys.
+	fn new_validator_ext() -> sp_io::TestExternalities {
+		let validators = [
+			(Sr25519Keyring::Alice.public().0, Ed25519Keyring::Alice.public().0),
+			(Sr25519Keyring::Bob.public().0, Ed25519Keyring::Bob.public().0),
+		];
+		GenesisConfig { session: test_session_config(&validators), ..Default::default() }
+			.build_storage()
+			.unwrap()
+			.into()
+	}
+
+	/// `offender` prevoting for two different blocks at height 1 in the same round.
+	fn prevote_equivocation(
+		offender: Ed25519Keyring,
+		targets: (H256, H256),
+	) -> fg_primitives::EquivocationProof<H256, u32> {
+		let set_id = Grandpa::current_set_id();
+		let round = 1;
+		let prevote = |target_hash| {
+			let prevote = finality_grandpa::Prevote { target_hash, target_number: 1 };
+			let message = finality_grandpa::Message::Prevote(prevote.clone());
+			let payload = fg_primitives::localized_payload(round, set_id, &message);
+			(prevote, offender.sign(&payload).into())
+		};
+		fg_primitives::EquivocationProof::new(
+			set_id,
+			fg_primitives::Equivocation::Prevote(finality_grandpa::Equivocation {
+				round_number: round,
+				identity: offender.public().into(),
+				first: prevote(targets.0),
+				second: prevote(targets.1),
+			}),
+		)
+	}
+
+	fn key_ownership_proof(keyring: Ed25519Keyring) -> sp_session::MembershipProof {
+		Historical::prove((fg_primitives::KEY_TYPE, GrandpaId::from(keyring.public()))).unwrap()
+	}
+
+	fn offenders() -> Vec<(AccountId, AccountId)> {
+		pallet_offences::Reports::<Runtime>::iter_values()
+			.map(|report| report.offender)
+			.collect()
+	}
+
+	#[test]
+	fn genesis_session_keys_become_authorities() {
+		new_validator_ext().execute_with(|| {
+			let mut validators =
+				vec![Sr25519Keyring::Alice.to_account_id(), Sr25519Keyring::Bob.to_account_id()];
+			validators.sort();
+			assert_eq!(Session::validators(), validators);
+			assert_eq!(Aura::authorities().len(), 2);
+			assert_eq!(Grandpa::grandpa_authorities().len(), 2);
+		});
+	}
+
+	#[test]
+	fn equivocations_are_recorded_as_offences() {
+		new_validator_ext().execute_with(|| {
+			System::set_block_number(1);
+			let bob = Sr25519Keyring::Bob.to_account_id();
+			let proof = prevote_equivocation(
+				Ed25519Keyring::Bob,
+				(H256::repeat_byte(1), H256::repeat_byte(2)),
+			);
+			let key_owner_proof = key_ownership_proof(Ed25519Keyring::Bob);
+
+			assert_ok!(Grandpa::report_equivocation_unsigned(
+				Origin::none(),
+				Box::new(proof.clone()),
+				key_owner_proof.clone(),
+			));
+
+			assert_eq!(offenders(), vec![(bob.clone(), bob)]);
+			assert!(System::events().iter().any(|record| matches!(
+				record.event,
+				Event::Offences(pallet_offences::Event::Offence { .. })
+			)));
+			assert_err!(
+				Grandpa::report_equivocation_unsigned(
+					Origin::none(),
+					Box::new(proof),
+					key_owner_proof
+				),
+				pallet_grandpa::Error::<Runtime>::DuplicateOffenceReport
+			);
+		});
+	}
+
+	#[test]
+	fn key_ownership_proofs_outlive_their_session() {
+		new_validator_ext().execute_with(|| {
+			System::set_block_number(1);
+			let proof = prevote_equivocation(
+				Ed25519Keyring::Alice,
+				(H256::repeat_byte(1), H256::repeat_byte(2)),
+			);
+			let key_owner_proof = key_ownership_proof(Ed25519Keyring::Alice);
+
+			Session::rotate_session();
+			Session::rotate_session();
+			assert_eq!(Session::current_index(), 2);
+			// Nobody rotated their keys, so GRANDPA keeps its authority set.
+			assert_eq!(Grandpa::current_set_id(), 0);
+
+			assert_ok!(Grandpa::report_equivocation_unsigned(
+				Origin::none(),
+				Box::new(proof),
+				key_owner_proof,
+			));
+			let alice = Sr25519Keyring::Alice.to_account_id();
+			assert_eq!(offenders(), vec![(alice.clone(), alice)]);
+		});
+	}
+
+	#[test]
+	fn old_historical_roots_are_pruned() {
+		new_validator_ext().execute_with(|| {
+			let kept = KeptHistoricalSessions::get();
+			for _ in 0..2 * kept {
+				Session::rotate_session();
+			}
+
+			let newest = Session::current_index() + 1;
+			let stored = (0..=newest).filter(|i| Historical::historical_root(*i).is_some()).count();
+			assert!(Historical::historical_root(0).is_none());
+			assert!(Historical::historical_root(newest).is_some());
+			assert!(stored <= kept as usize + 1, "{} roots stored", stored);
+		});
+	}
+
+	#[test]
+	fn invalid_equivocation_reports_are_rejected() {
+		new_validator_ext().execute_with(|| {
+			System::set_block_number(1);
+			let report = |proof, key_owner_proof| {
+				Grandpa::report_equivocation_unsigned(
+					Origin::none(),
+					Box::new(proof),
+					key_owner_proof,
+				)
+			};
+
+			// Voting twice for the same block is no equivocation.
+			let same_vote = prevote_equivocation(
+				Ed25519Keyring::Bob,
+				(H256::repeat_byte(1), H256::repeat_byte(1)),
+			);
+			assert_err!(
+				report(same_vote, key_ownership_proof(Ed25519Keyring::Bob)),
+				pallet_grandpa::Error::<Runtime>::InvalidEquivocationProof
+			);
+
+			// The key ownership proof must be the offender's.
+			let proof = prevote_equivocation(
+				Ed25519Keyring::Bob,
+				(H256::repeat_byte(1), H256::repeat_byte(2)),
+			);
+			assert_err!(
+				report(proof.clone(), key_ownership_proof(Ed25519Keyring::Alice)),
+				pallet_grandpa::Error::<Runtime>::InvalidKeyOwnershipProof
+			);
+
+			// Only validators' keys can be proven.
+			let charlie = GrandpaId::from(Ed25519Keyring::Charlie.public());
+			assert!(Historical::prove((fg_primitives::KEY_TYPE, charlie)).is_none());
+
+			assert!(offenders().is_empty());
+		});
+	}
+}
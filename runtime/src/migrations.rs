@@ -0,0 +1,80 @@
+//! Storage migrations for the node template runtime.
+
+use frame_support::{
+	storage::unhashed,
+	traits::{Get, OnRuntimeUpgrade},
+	weights::Weight,
+};
+use sp_core::hashing::twox_128;
+use sp_io::KillStorageResult;
+
+/// Removes the whole `Sudo` pallet storage, key included, once governance has taken over.
+///
+/// Ship it in the runtime upgrade that drops `Sudo` from `construct_runtime!` by adding it to the
+/// `Executive`'s migrations. From then on the root origin is only reachable through democracy.
+pub struct RemoveSudo<T>(sp_std::marker::PhantomData<T>);
+impl<T: frame_system::Config> OnRuntimeUpgrade for RemoveSudo<T> {
+	fn on_runtime_upgrade() -> Weight {
+		let removed = match unhashed::kill_prefix(&twox_128(b"Sudo"), None) {
+			KillStorageResult::AllRemoved(removed) | KillStorageResult::SomeRemaining(removed) =>
+				removed,
+		};
+		T::DbWeight::get().writes(removed.into())
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn pre_upgrade() -> Result<(), &'static str> {
+		if sudo_storage_exists() {
+			Ok(())
+		} else {
+			Err("Sudo storage is already gone")
+		}
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade() -> Result<(), &'static str> {
+		if sudo_storage_exists() {
+			Err("Sudo storage was not cleared")
+		} else {
+			Ok(())
+		}
+	}
+}
+
+/// Whether any storage is left under the `Sudo` pallet prefix.
+#[cfg(any(test, feature = "try-runtime"))]
+fn sudo_storage_exists() -> bool {
+	let prefix = twox_128(b"Sudo");
+	sp_io::storage::next_key(&prefix).map_or(false, |key| key.starts_with(&prefix))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		impls::test_session_config, BuildStorage, GenesisConfig, Runtime, Sudo, SudoConfig,
+	};
+	use sp_core::crypto::AccountId32;
+
+	#[test]
+	fn remove_sudo_clears_the_pallet_storage() {
+		let mut ext: sp_io::TestExternalities = GenesisConfig {
+			sudo: SudoConfig { key: Some(AccountId32::new([1; 32])) },
+			session: test_session_config(&[([1; 32], [1; 32])]),
+			..Default::default()
+		}
+		.build_storage()
+		.unwrap()
+		.into();
+
+		ext.execute_with(|| {
+			let other = [twox_128(b"Sudo"), twox_128(b"Other")].concat();
+			unhashed::put(&other, &1u32);
+			assert!(Sudo::key().is_some());
+
+			RemoveSudo::<Runtime>::on_runtime_upgrade();
+			assert_eq!(Sudo::key(), None);
+			assert!(!sudo_storage_exists());
+		});
+	}
+}